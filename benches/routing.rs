@@ -31,9 +31,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             //
             Ok(())
         }),
-        Seg::new("divide").operands(1).nest(&[Seg::new("example")]),
+        Seg::new("divide").operands(1..=1).nest(&[Seg::new("example")]),
         Seg::new("print")
-            .operands(1)
+            .operands(1..=1)
             .action(|_| Ok(println!("I'm printing!"))),
     ]);
     pub const SMALL_R: Router = router!(Small_O, SMALL_C);
@@ -13,10 +13,10 @@ fn main() {
     const C: Seg = Seg::new("example").nest(&[
         Seg::new("add")
             .action(|_| Ok(println!("Add some things")))
-            .operands(2),
+            .operands(2..=2),
         Seg::new("divide").action(|_| Ok(println!("Divide two things."))),
         Seg::new("print").action(|_| Ok(println!("I'm printing"))),
-        Seg::new("help").operands(u16::MAX).action(|c| {
+        Seg::new("help").operands(0..=u16::MAX).action(|c| {
             let command = c
                 .router
                 .parse(c.operands().into_iter().map(|s| s.to_owned()))?
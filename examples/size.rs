@@ -16,7 +16,7 @@ optmap!(enum O using [
 
 fn main() {
     const C: Seg = Seg::new("example").nest(&[
-        Seg::new("add").action(|_| Ok(())).operands(u16::MAX),
+        Seg::new("add").action(|_| Ok(())).operands(0..=u16::MAX),
         Seg::new("divide").nest(&[Seg::new("example")
         .options(&[OptGroup::anyof(&[O::ValueArg])])
         .action(|c| {
@@ -24,7 +24,7 @@ fn main() {
             Ok(())
         })]),
         Seg::new("print")
-            .operands(1)
+            .operands(1..=1)
             .action(|_| Ok(println!("I'm printing!"))),
     ]);
     const R: Router = router!(O, C);
@@ -20,7 +20,7 @@ fn main() {
             //
             Ok(())
         }),
-        Seg::new("divide").operands(1).nest(&[Seg::new("example")
+        Seg::new("divide").operands(1..=1).nest(&[Seg::new("example")
             .options(&[OptGroup::anyof(&[O::ValueArg])])
             .action(|c| {
                 // println!(
@@ -30,7 +30,7 @@ fn main() {
                 Ok(())
             })]),
         Seg::new("print")
-            .operands(1)
+            .operands(1..=1)
             .action(|_| Ok(println!("I'm printing!"))),
     ]);
     const R: Router = router!(O, C);
@@ -17,7 +17,7 @@ fn main() {
     const C: Seg = Seg::new("example").nest(&[
         Seg::new("add")
             .action(|_| Ok(println!("Add some things")))
-            .operands(2),
+            .operands(2..=2),
         Seg::new("divide").action(|_| Ok(println!("Divide two things."))),
         // .doc(|_, blocks| blocks.summary("div summary!")),
         Seg::new("print").action(|_| Ok(println!("I'm printing"))),
@@ -23,7 +23,7 @@ fn main_without_doc_method() {
 
                 Ok(println!("Add some things"))
             })
-            .operands(2),
+            .operands(2..=2),
         Seg::new("divide", "").action(|c| {
             if c.has_opt(O::Help) {
                 let docs = "Could be generated however you want";
@@ -44,7 +44,7 @@ fn main_with_doc_method_only() {
     const C: Seg = Seg::new("example", "").nest(&[
         Seg::new("add", "")
             .action(|_| Ok(println!("Add some things")))
-            .operands(2)
+            .operands(2..=2)
             .doc(|_| {
                 let complete_docs = fs::read_to_string("path/to/docs")
                     .unwrap_or("No docs".to_string());
@@ -68,7 +68,7 @@ fn main_with_global_handler_and_doc_method() {
     const C: Seg = Seg::new("example", "").nest(&[
         Seg::new("add", "")
             .action(|_| Ok(println!("Add some things")))
-            .operands(2)
+            .operands(2..=2)
             .doc(|_, _unused_blocks| {
                 let complete_docs = fs::read_to_string("path/to/docs")
                     .unwrap_or("No docs".to_string());
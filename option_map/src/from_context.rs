@@ -0,0 +1,314 @@
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+use std::str::FromStr;
+
+/// Where a field's value comes from
+enum Source {
+    /// An `optmap!()` variant, e.g. `O::Width`
+    Opt(String),
+    /// All of the operands
+    Operands,
+    /// The operand at an index, or with a name when it's a string
+    Operand(String),
+    /// The path parameter at an index, or with a name when it's
+    /// a string
+    PathParam(String),
+}
+
+/// How a field's type wraps the converted value
+enum Shape {
+    Flag,
+    Optional(String),
+    List(String),
+    Required(String),
+}
+
+struct Field {
+    name: String,
+    source: Source,
+    shape: Shape,
+}
+
+/// The tokens of a type, and whether it's `Option<T>`, `Vec<T>`,
+/// or `bool`
+fn shape_of(ty: &[TokenTree]) -> Shape {
+    let text = ty.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    if text.len() == 1 && text[0] == "bool" {
+        return Shape::Flag;
+    }
+    if text.len() > 3 && text[1] == "<" && text[text.len() - 1] == ">" {
+        let inner = text[2..text.len() - 1].join(" ");
+        match text[0].as_str() {
+            "Option" => return Shape::Optional(inner),
+            "Vec" => return Shape::List(inner),
+            _ => {}
+        }
+    }
+    Shape::Required(text.join(" "))
+}
+
+/// The field's source from an attribute's tokens, e.g.
+/// `opt(O::Width)`, or `None` for other attributes like docs
+fn source_of(attr: TokenStream) -> Option<Source> {
+    let mut tokens = attr.into_iter();
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return None,
+    };
+    let arg = match tokens.next() {
+        Some(TokenTree::Group(g))
+            if g.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(g.stream().to_string())
+        }
+        _ => None,
+    };
+    let expect_arg = |arg: Option<String>, example: &str| {
+        arg.unwrap_or_else(|| {
+            panic!(
+                "The `{}` attribute expects an argument, e.g. `#[{}]`",
+                name, example
+            )
+        })
+    };
+    match name.as_str() {
+        "opt" => Some(Source::Opt(expect_arg(arg, "opt(O::Width)"))),
+        "operands" => Some(Source::Operands),
+        "operand" => Some(Source::Operand(expect_arg(arg, "operand(0)"))),
+        "path_param" => {
+            Some(Source::PathParam(expect_arg(arg, "path_param(0)")))
+        }
+        _ => None,
+    }
+}
+
+/// Read the fields of a struct's body
+fn fields(body: TokenStream) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let mut tokens = body.into_iter().peekable();
+    let mut source = None;
+    while let Some(t) = tokens.next() {
+        match t {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                if let Some(TokenTree::Group(g)) = tokens.next() {
+                    if let Some(s) = source_of(g.stream()) {
+                        source = Some(s);
+                    }
+                }
+            }
+            TokenTree::Ident(ident) if ident.to_string() == "pub" => {
+                // Skip a restriction like `(crate)`
+                if let Some(TokenTree::Group(_)) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                match tokens.next() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' => {}
+                    _ => {
+                        panic!("Expected a `:` after the field `{}`", name)
+                    }
+                }
+                // Commas inside angle brackets belong to the type
+                let mut depth = 0;
+                let mut ty = Vec::new();
+                for t in tokens.by_ref() {
+                    if let TokenTree::Punct(p) = &t {
+                        match p.as_char() {
+                            '<' => depth += 1,
+                            '>' => depth -= 1,
+                            ',' if depth == 0 => break,
+                            _ => {}
+                        }
+                    }
+                    ty.push(t);
+                }
+                let shape = shape_of(&ty);
+                let source = source.take().unwrap_or_else(|| {
+                    panic!(
+                        "The field `{}` needs one of `#[opt(..)]`, `#[operands]`, `#[operand(..)]`, or `#[path_param(..)]`",
+                        name
+                    )
+                });
+                if let Shape::Flag = shape {
+                    if !matches!(source, Source::Opt(_)) {
+                        panic!(
+                            "The field `{}` can only be a `bool` when it's from an option",
+                            name
+                        );
+                    }
+                }
+                fields.push(Field {
+                    name,
+                    source,
+                    shape,
+                });
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// An expression converting `a`, an `&OsString`, to `ty`, with
+/// the error as a `String`
+fn convert(ty: &str) -> String {
+    format!(
+        "<{} as router::FromOsStr>::from_os_str(a).map_err(|e|format!(\"Invalid value \\\"{{}}\\\": {{}}\",a.to_string_lossy(),e))",
+        ty
+    )
+}
+
+/// An expression converting a single argument from `get`, an
+/// expression of an `Option<&OsString>`
+fn single(get: &str, shape: &Shape, missing: &str) -> String {
+    match shape {
+        Shape::Optional(ty) => {
+            format!("{}.map(|a|{}).transpose()", get, convert(ty))
+        }
+        Shape::Required(ty) => format!(
+            "{}.ok_or_else(||\"{}\".to_string()).and_then(|a|{})",
+            get,
+            missing,
+            convert(ty)
+        ),
+        _ => panic!(
+            "Only `Option<T>` or `T` can be taken from one {}",
+            missing
+        ),
+    }
+}
+
+/// An expression of the field's value as a `Result<T, String>`
+fn value_of(field: &Field) -> String {
+    match (&field.source, &field.shape) {
+        (Source::Opt(opt), Shape::Flag) => {
+            format!("Ok(c.option_occurrences({})>0)", opt)
+        }
+        (Source::Opt(opt), Shape::Optional(ty)) => format!(
+            "c.opt({}).value_os::<{}>().map_err(|e|e.to_string())",
+            opt, ty
+        ),
+        (Source::Opt(opt), Shape::List(ty)) => format!(
+            "c.opt({}).values_os::<{}>().map_err(|e|e.to_string())",
+            opt, ty
+        ),
+        (Source::Opt(opt), Shape::Required(ty)) => format!(
+            "c.opt({}).value_os::<{}>().map_err(|e|e.to_string()).and_then(|v|v.ok_or_else(||\"Missing a required option\".to_string()))",
+            opt, ty
+        ),
+        (Source::Operands, Shape::List(ty)) => format!(
+            "c.operands().iter().map(|a|{}).collect::<Result<Vec<{}>,String>>()",
+            convert(ty),
+            ty
+        ),
+        (Source::Operands, _) => panic!(
+            "The field `{}` must be a `Vec<T>` to take all operands",
+            field.name
+        ),
+        (Source::Operand(name), Shape::List(ty)) if name.starts_with('"') => {
+            format!(
+                "c.named_operand({}).iter().map(|a|{}).collect::<Result<Vec<{}>,String>>()",
+                name,
+                convert(ty),
+                ty
+            )
+        }
+        (Source::Operand(name), shape) if name.starts_with('"') => single(
+            &format!("c.operand({})", name),
+            shape,
+            "Missing a required operand",
+        ),
+        (Source::Operand(index), shape) => single(
+            &format!("c.operands().get({})", index),
+            shape,
+            "Missing a required operand",
+        ),
+        (Source::PathParam(name), shape) if name.starts_with('"') => {
+            single(
+                &format!("c.path_param({})", name),
+                shape,
+                "Missing a required path parameter",
+            )
+        }
+        (Source::PathParam(index), shape) => single(
+            &format!("c.path_params().get({})", index),
+            shape,
+            "Missing a required path parameter",
+        ),
+    }
+}
+
+pub fn derive(input: TokenStream) -> TokenStream {
+    let mut input = input.into_iter();
+    let mut name = None;
+    let mut body = None;
+    while let Some(t) = input.next() {
+        match t {
+            TokenTree::Ident(ident) if ident.to_string() == "struct" => {
+                match input.next() {
+                    Some(TokenTree::Ident(ident)) => {
+                        name = Some(ident.to_string())
+                    }
+                    _ => panic!("Expected the struct's name"),
+                }
+                match input.next() {
+                    Some(TokenTree::Group(g))
+                        if g.delimiter() == Delimiter::Brace =>
+                    {
+                        body = Some(g.stream())
+                    }
+                    Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+                        panic!("`FromContext` doesn't support generics")
+                    }
+                    _ => panic!(
+                        "`FromContext` only supports structs with named fields"
+                    ),
+                }
+                break;
+            }
+            TokenTree::Ident(ident)
+                if ident.to_string() == "enum"
+                    || ident.to_string() == "union" =>
+            {
+                panic!("`FromContext` only supports structs")
+            }
+            _ => {}
+        }
+    }
+    let (name, body) = match (name, body) {
+        (Some(name), Some(body)) => (name, body),
+        _ => panic!("Expected a struct"),
+    };
+    let fields = fields(body);
+
+    let mut out = String::with_capacity(256);
+    out.push_str("impl<'a,'b> ::std::convert::TryFrom<&'a router::Context<'b>> for ");
+    out.push_str(&name);
+    out.push_str(
+        "{type Error=router::FieldErrors;
+        fn try_from(c:&'a router::Context<'b>)->Result<Self,Self::Error>{
+          let mut errors=Vec::new();",
+    );
+    // The locals are prefixed so that a field can't shadow `c` or
+    // `errors`
+    for field in fields.iter() {
+        out.push_str("let __field_");
+        out.push_str(field.name.trim_start_matches("r#"));
+        out.push_str("=match ");
+        out.push_str(&value_of(field));
+        out.push_str("{Ok(v)=>Some(v),Err(message)=>{errors.push(router::FieldError{field:\"");
+        out.push_str(field.name.trim_start_matches("r#"));
+        out.push_str("\",message});None}};");
+    }
+    out.push_str("if !errors.is_empty(){return Err(router::FieldErrors(errors));}Ok(Self{");
+    for field in fields.iter() {
+        out.push_str(&field.name);
+        out.push_str(":__field_");
+        out.push_str(field.name.trim_start_matches("r#"));
+        out.push_str(".unwrap(),");
+    }
+    out.push_str("})}}");
+
+    TokenStream::from_str(&out).unwrap()
+}
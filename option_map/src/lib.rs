@@ -2,18 +2,213 @@ use proc_macro::{Delimiter, TokenStream, TokenTree};
 // Necessary for the `TokenStream::from_str()` implementation
 use std::str::FromStr;
 
+mod from_context;
+
 enum OptArg {
     None,
     Single,
     Multi,
+    /// `KEY=VALUE` pairs
+    Pairs,
+    /// Only takes an attached option-argument
+    Optional,
+    /// Takes an option-argument of each type every time
+    Tuple(Vec<String>),
 }
 enum NameCase {
     Lower,
     Sentence,
 }
 
+/// Values from attributes that configure the `router::Opt`
+#[derive(Default)]
+struct OptAttrs {
+    /// A string literal, including quotes
+    default: Option<String>,
+    /// A string literal, including quotes
+    env: Option<String>,
+    /// A `router::Repeat` expression
+    repeat: Option<String>,
+    /// A char literal
+    delimiter: Option<String>,
+    /// A string literal, including quotes
+    implicit: Option<String>,
+    /// A string literal, including quotes
+    value_name: Option<String>,
+    /// A slice of string literals
+    values: Option<String>,
+    /// A range expression
+    range: Option<String>,
+    /// A string literal, including quotes
+    chars: Option<String>,
+}
+
+// (option name, summary, type, the option's attribute tokens, shorthand, opt arg kind, name case, attributes)
+type Variant = (
+    String,
+    String,
+    String,
+    String,
+    Option<char>,
+    OptArg,
+    NameCase,
+    OptAttrs,
+);
+
+/// The value of a string literal's tokens, e.g. `"a\tb"` or
+/// `r"a\b"`, or None when they're another kind of literal
+fn string_literal(text: &str) -> Option<String> {
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw[hashes..]
+            .strip_prefix('"')?
+            .strip_suffix(&raw[..hashes])?
+            .strip_suffix('"')
+            .map(String::from);
+    }
+    let mut chars = text.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut value = String::with_capacity(text.len());
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            c @ ('\\' | '\'' | '"') => value.push(c),
+            'x' => {
+                let hex = [chars.next()?, chars.next()?];
+                let code = u8::from_str_radix(&String::from_iter(hex), 16)
+                    .ok()
+                    .filter(u8::is_ascii)?;
+                value.push(code as char);
+            }
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (hex, rest) = rest.split_once('}')?;
+                let code = u32::from_str_radix(&hex.replace('_', ""), 16)
+                    .ok()
+                    .and_then(char::from_u32)?;
+                value.push(code);
+                chars = rest.chars();
+            }
+            // A line continuation skips the whitespace after it
+            '\n' => {
+                chars = chars.as_str().trim_start().chars();
+            }
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+/// The `router::Repeat` for a `repeat` attribute's string
+fn repeat_policy(text: &str) -> String {
+    let text = text.trim();
+    match text {
+        "last" => "router::Repeat::Last".to_string(),
+        "first" => "router::Repeat::First".to_string(),
+        "error" => "router::Repeat::Error".to_string(),
+        _ => match text
+            .strip_prefix("max")
+            .and_then(|n| n.trim().parse::<u16>().ok())
+        {
+            Some(max) => format!("router::Repeat::Max({})", max),
+            None => panic!(
+                "Invalid value for `repeat` attribute, expected \"last\", \"first\", \"error\", or \"max N\""
+            ),
+        },
+    }
+}
+
+/// The char literal for a `#[delimiter = ".."]` attribute's value
+fn delimiter_char(text: &str) -> String {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() && c != '\\' => format!("{:?}", c),
+        _ => panic!(
+            "The `delimiter` attribute expects one ASCII character other than '\\', e.g. `#[delimiter = \",\"]`"
+        ),
+    }
+}
+
+/// Read the types of a tuple option-argument, e.g.
+/// `(String, u32)`, returning the tuple type
+fn tuple_type(group: &proc_macro::Group, kind: &mut OptArg) -> String {
+    let mut types = Vec::new();
+    for tok in group.stream() {
+        match tok {
+            TokenTree::Ident(t) => types.push(t.to_string()),
+            TokenTree::Punct(p) if p.as_char() == ',' => (),
+            _ => panic!("A tuple option-argument expects types, e.g. `(String, u32)`"),
+        }
+    }
+    if types.is_empty() || types.len() > u8::MAX as usize {
+        panic!("A tuple option-argument expects 1 to 255 types");
+    }
+    let tuple = format!("({},)", types.join(","));
+    *kind = OptArg::Tuple(types);
+    tuple
+}
+
+/// A type without `[]`, `{}`, or `?` after it takes one
+/// option-argument, unless it's a tuple
+fn set_single(kind: &mut OptArg) {
+    if let OptArg::None = kind {
+        *kind = OptArg::Single;
+    }
+}
+
+/// The slice of a `#[values = "a|b"]` attribute's values
+fn possible_values(text: &str) -> String {
+    let mut values = String::from("&[");
+    for value in text.split('|') {
+        if value.is_empty() {
+            panic!("The `values` attribute expects values separated by '|', e.g. `#[values = \"json|yaml\"]`");
+        }
+        values.push_str(&format!("{:?},", value));
+    }
+    values.push(']');
+    values
+}
+
+fn empty_variant() -> Variant {
+    (
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+        None,
+        OptArg::None,
+        NameCase::Lower,
+        OptAttrs::default(),
+    )
+}
+
+/// The kind of option an empty `[]` or `{}` after the
+/// option-argument's type makes
+fn list_kind(group: &proc_macro::Group) -> OptArg {
+    match group.delimiter() {
+        Delimiter::Brace => OptArg::Pairs,
+        _ => OptArg::Multi,
+    }
+}
+
 const MISSING_OPT_ARG_IDENT_MSG: &'static str =
-  "Missing an identifier for the option-argument. Currently, this is limited to `String`";
+  "Missing the option-argument's type, e.g. `String`. It must implement `router::FromOsStr`";
+
+// Words that need a raw identifier to be used as an accessor
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else",
+    "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let",
+    "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
 
 fn to_case(ident: &str, case: &NameCase) -> String {
     let mut out = String::with_capacity(ident.len());
@@ -51,9 +246,27 @@ fn to_case(ident: &str, case: &NameCase) -> String {
     out
 }
 
+/// The name of the function that gets an option's typed value
+fn accessor_name(ident: &str) -> String {
+    let name = to_case(ident, &NameCase::Lower).replace('-', "_");
+    if name == "list" {
+        panic!("An option named `{}` conflicts with the generated `list()` function", ident);
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        return format!("r#{}", name);
+    }
+    name
+}
+
 /// Define the options available to the program.
 /// The output is an enum that can be matched on.
 ///
+/// An option that expects an option-argument declares its
+/// type, which must implement `router::FromOsStr`. The type is
+/// checked while parsing, and the enum gets a function named
+/// after the option to get its value(s), e.g.
+/// `O::width(&context)` for `Width > u16`.
+///
 /// If constructing the `Router` manually, not using
 /// `router!()`, the generated enum has a `list()`
 /// method to supply option data.
@@ -68,10 +281,27 @@ fn to_case(ident: &str, case: &NameCase) -> String {
 ///   Variant2 | 'a',
 ///   // A shorthand-only variant
 ///   'Q'
-///   // A variant that expects one argument (Must be `String`)
+///   // A variant that expects one argument
 ///   Variant3 > String,
-///   // A variant that expects one or more arguments (Must be `String`)
-///   Variant5 > String[],
+///   // A variant that expects one or more arguments
+///   Variant5 > u32[],
+///   // A variant that expects `KEY=VALUE` pairs, e.g. `-D a=1`
+///   Define | 'D' > String{},
+///   // A variant whose argument can be left out, e.g. `--color`
+///   // or `--color=never`, which is "always" when left out
+///   #[implicit = "always"]
+///   #[value_name = "WHEN"]
+///   Color > String?,
+///   // A variant that expects 2 arguments each time, e.g.
+///   // `--rename old new`
+///   Rename > (String, String),
+///   // Variants that only accept some values
+///   #[values = "json|yaml|table"]
+///   Format > String,
+///   #[range = "1..=256"]
+///   Jobs | 'j' > u16,
+///   #[chars = "a-z0-9_-"]
+///   Name > String,
 ///   // A variant with a short alias and an argument
 ///   Variant4 | 'b' > String,
 ///   /// This doc comment will become the option's summary text
@@ -79,8 +309,44 @@ fn to_case(ident: &str, case: &NameCase) -> String {
 ///   /// Can change the case of the name: Sentence
 ///   #[case="sentence"]
 ///   SentenceCase,
+///   /// Uses the `APP_WIDTH` environment variable, then "80",
+///   /// when it isn't given
+///   #[env = "APP_WIDTH"]
+///   #[default = "80"]
+///   Width > u16,
+///   /// Can only be given once
+///   #[repeat = "error"]
+///   Output > String,
 /// ]);
 /// ```
+///
+/// A value from the command line takes precedence over the
+/// `env` variable, which takes precedence over the `default`.
+/// An empty environment variable counts as not being set.
+///
+/// `#[repeat = ".."]` chooses what happens when the option is
+/// given more than once: "last" (the default), "first",
+/// "error", or "max N" to ignore occurrences after N.
+///
+/// `#[delimiter = ","]` splits each option-argument of a `[]` or
+/// `{}` option, so `--tags a,b --tags c` gives three values.
+/// Empty elements are dropped, and `\,` is a literal ','.
+///
+/// An optional option-argument (`?`) is only taken when it's
+/// attached, as in `--color=never` or `-cnever`, so a bare
+/// `--color` never takes the next argument. `#[implicit = ".."]`
+/// is its value when it's left out, and `#[value_name = ".."]`
+/// names it in the help, as in `--color[=WHEN]`.
+///
+/// A tuple takes one option-argument for each of its types every
+/// time the option is given, and its accessor returns a tuple for
+/// each occurrence.
+///
+/// `#[values = "a|b"]` only accepts the listed values, which are
+/// shown in the help. `#[range = "1..=256"]` only accepts values
+/// of the type in the range. `#[chars = "a-z0-9_-"]` only accepts
+/// non-empty values made of the characters in the class, where
+/// a '-' at the start or end is a literal '-'.
 #[proc_macro]
 pub fn optmap(input: TokenStream) -> TokenStream {
     let mut input = input.into_iter();
@@ -89,16 +355,7 @@ pub fn optmap(input: TokenStream) -> TokenStream {
     // faster. I guess it's because the string form is much
     // more compact, being much less code to compile
     let mut out = String::with_capacity(42);
-    // (option name, summary, type, the option's attribute tokens, shorthand, opt arg kind)
-    let mut opt_variants = Vec::<(
-        String,
-        String,
-        String,
-        String,
-        Option<char>,
-        OptArg,
-        NameCase,
-    )>::new();
+    let mut opt_variants = Vec::<Variant>::new();
 
     out.push_str("#[repr(u16)]#[derive(Clone,Copy)]");
 
@@ -149,15 +406,7 @@ pub fn optmap(input: TokenStream) -> TokenStream {
     let mut wordbreak = false;
 
     // Add one so we don't have to check in the loop
-    opt_variants.push((
-        String::new(),
-        String::new(),
-        String::new(),
-        String::new(),
-        None,
-        OptArg::None,
-        NameCase::Lower,
-    ));
+    opt_variants.push(empty_variant());
     let mut variant = 0;
     while let Some(t) = input.next() {
         // Looking for variant, or attribute
@@ -220,6 +469,69 @@ pub fn optmap(input: TokenStream) -> TokenStream {
                                     };
                                 }
                             }
+                            Some(tok)
+                                if tok.to_string() == "default"
+                                    || tok.to_string() == "env"
+                                    || tok.to_string() == "repeat"
+                                    || tok.to_string() == "delimiter"
+                                    || tok.to_string() == "implicit"
+                                    || tok.to_string() == "value_name"
+                                    || tok.to_string() == "values"
+                                    || tok.to_string() == "range"
+                                    || tok.to_string() == "chars" =>
+                            {
+                                let name = tok.to_string();
+                                // Skip the '=' token
+                                attr_tokens.next();
+                                let text = match attr_tokens
+                                    .next()
+                                    .and_then(|tok| match tok {
+                                        TokenTree::Literal(lit) => {
+                                            string_literal(&lit.to_string())
+                                        }
+                                        _ => None,
+                                    }) {
+                                    Some(text) => text,
+                                    None => panic!(
+                                        "The `{}` attribute expects a string, e.g. `#[{} = \"value\"]`",
+                                        name, name
+                                    ),
+                                };
+                                // Emitted again as a plain literal
+                                let literal = format!("{:?}", text);
+                                if name == "default" {
+                                    opt_variants[variant].7.default =
+                                        Some(literal);
+                                } else if name == "repeat" {
+                                    opt_variants[variant].7.repeat =
+                                        Some(repeat_policy(&text));
+                                } else if name == "delimiter" {
+                                    opt_variants[variant].7.delimiter =
+                                        Some(delimiter_char(&text));
+                                } else if name == "implicit" {
+                                    opt_variants[variant].7.implicit =
+                                        Some(literal);
+                                } else if name == "value_name" {
+                                    opt_variants[variant].7.value_name =
+                                        Some(literal);
+                                } else if name == "values" {
+                                    opt_variants[variant].7.values =
+                                        Some(possible_values(&text));
+                                } else if name == "range" {
+                                    let range = text;
+                                    if !range.contains("..") {
+                                        panic!("The `range` attribute expects a range, e.g. `#[range = \"1..=256\"]`");
+                                    }
+                                    opt_variants[variant].7.range =
+                                        Some(range);
+                                } else if name == "chars" {
+                                    opt_variants[variant].7.chars =
+                                        Some(literal);
+                                } else {
+                                    opt_variants[variant].7.env =
+                                        Some(literal);
+                                }
+                            }
                             Some(_) => {
                                 opt_variants[variant].3.push('#');
                                 opt_variants[variant]
@@ -280,15 +592,7 @@ pub fn optmap(input: TokenStream) -> TokenStream {
             Some(TokenTree::Punct(p)) => match p.as_char() {
                 ',' => {
                     variant += 1;
-                    opt_variants.push((
-                        String::new(),
-                        String::new(),
-                        String::new(),
-                        String::new(),
-                        None,
-                        OptArg::None,
-                        NameCase::Lower,
-                    ));
+                    opt_variants.push(empty_variant());
                     continue;
                 }
                 '|' => {
@@ -321,28 +625,27 @@ pub fn optmap(input: TokenStream) -> TokenStream {
                     // Add value to variant
                     opt_variants[variant].2 = match input.next() {
                         Some(TokenTree::Ident(v)) => v.to_string(),
+                        Some(TokenTree::Group(g))
+                            if g.delimiter() == Delimiter::Parenthesis =>
+                        {
+                            tuple_type(&g, &mut opt_variants[variant].5)
+                        }
                         _ => panic!("{}", MISSING_OPT_ARG_IDENT_MSG),
                     };
                     match input.next() {
                         Some(TokenTree::Group(g))
-                            if g.delimiter() == Delimiter::Bracket
-                                && g.stream().is_empty() =>
+                            if matches!(
+                                g.delimiter(),
+                                Delimiter::Bracket | Delimiter::Brace
+                            ) && g.stream().is_empty() =>
                         {
-                            opt_variants[variant].5 = OptArg::Multi;
+                            opt_variants[variant].5 = list_kind(&g);
                             match input.next() {
                                 Some(TokenTree::Punct(p))
                                     if p.as_char() == ',' =>
                                 {
                                     variant += 1;
-                                    opt_variants.push((
-                                        String::new(),
-                                        String::new(),
-                                        String::new(),
-                                        String::new(),
-                                        None,
-                                        OptArg::None,
-                                        NameCase::Lower,
-                                    ));
+                                    opt_variants.push(empty_variant());
                                     continue;
                                 }
                                 None => {
@@ -355,24 +658,34 @@ pub fn optmap(input: TokenStream) -> TokenStream {
                                 }
                             }
                         }
+                        Some(TokenTree::Punct(p))
+                            if p.as_char() == '?' =>
+                        {
+                            opt_variants[variant].5 = OptArg::Optional;
+                            match input.next() {
+                                Some(TokenTree::Punct(p))
+                                    if p.as_char() == ',' =>
+                                {
+                                    variant += 1;
+                                    opt_variants.push(empty_variant());
+                                    continue;
+                                }
+                                None => break,
+                                Some(tok) => {
+                                    panic!("Unexpected token {}", tok)
+                                }
+                            }
+                        }
                         Some(TokenTree::Punct(p))
                             if p.as_char() == ',' =>
                         {
-                            opt_variants[variant].5 = OptArg::Single;
+                            set_single(&mut opt_variants[variant].5);
                             variant += 1;
-                            opt_variants.push((
-                                String::new(),
-                                String::new(),
-                                String::new(),
-                                String::new(),
-                                None,
-                                OptArg::None,
-                                NameCase::Lower,
-                            ));
+                            opt_variants.push(empty_variant());
                             continue;
                         }
                         None => {
-                            opt_variants[variant].5 = OptArg::Single;
+                            set_single(&mut opt_variants[variant].5);
                             // No more tokens
                             break;
                         }
@@ -390,28 +703,31 @@ pub fn optmap(input: TokenStream) -> TokenStream {
                 match p.as_char() {
                     ',' => {
                         variant += 1;
-                        opt_variants.push((
-                            String::new(),
-                            String::new(),
-                            String::new(),
-                            String::new(),
-                            None,
-                            OptArg::None,
-                            NameCase::Lower,
-                        ));
+                        opt_variants.push(empty_variant());
                     }
                     '>' => {
                         // Add value to variant
                         opt_variants[variant].2 = match input.next() {
                             Some(TokenTree::Ident(v)) => v.to_string(),
+                            Some(TokenTree::Group(g))
+                                if g.delimiter()
+                                    == Delimiter::Parenthesis =>
+                            {
+                                tuple_type(
+                                    &g,
+                                    &mut opt_variants[variant].5,
+                                )
+                            }
                             _ => panic!("{}", MISSING_OPT_ARG_IDENT_MSG),
                         };
                         match input.next() {
                             Some(TokenTree::Group(g))
-                                if g.delimiter() == Delimiter::Bracket
-                                    && g.stream().is_empty() =>
+                                if matches!(
+                                    g.delimiter(),
+                                    Delimiter::Bracket | Delimiter::Brace
+                                ) && g.stream().is_empty() =>
                             {
-                                opt_variants[variant].5 = OptArg::Multi;
+                                opt_variants[variant].5 = list_kind(&g);
                                 match input.next() {
                                     Some(TokenTree::Punct(p))
                                         if p.as_char() == ',' => {}
@@ -424,28 +740,33 @@ pub fn optmap(input: TokenStream) -> TokenStream {
                                     }
                                 }
                             }
+                            Some(TokenTree::Punct(p))
+                                if p.as_char() == '?' =>
+                            {
+                                opt_variants[variant].5 = OptArg::Optional;
+                                match input.next() {
+                                    Some(TokenTree::Punct(p))
+                                        if p.as_char() == ',' => {}
+                                    None => break,
+                                    Some(tok) => {
+                                        panic!("Unexpected token {}", tok)
+                                    }
+                                }
+                            }
                             Some(TokenTree::Punct(p))
                                 if p.as_char() == ',' =>
                             {
-                                opt_variants[variant].5 = OptArg::Single;
+                                set_single(&mut opt_variants[variant].5);
                             }
                             None => {
-                                opt_variants[variant].5 = OptArg::Single;
+                                set_single(&mut opt_variants[variant].5);
                                 // No more tokens
                                 break;
                             }
                             _ => panic!("Unexpected token."),
                         }
                         variant += 1;
-                        opt_variants.push((
-                            String::new(),
-                            String::new(),
-                            String::new(),
-                            String::new(),
-                            None,
-                            OptArg::None,
-                            NameCase::Lower,
-                        ));
+                        opt_variants.push(empty_variant());
                         continue;
                     }
                     _ => {}
@@ -464,6 +785,7 @@ pub fn optmap(input: TokenStream) -> TokenStream {
     let mut summaries = String::new();
     let mut names = String::new();
     let mut enum_variants = String::new();
+    let mut accessors = String::new();
     variant = 0;
 
     // For binary search at runtime
@@ -482,14 +804,163 @@ pub fn optmap(input: TokenStream) -> TokenStream {
         enum_variants.push_str(&o.0);
         enum_variants.push(',');
 
-        router_opts.push_str("router::Opt{name:");
+        router_opts.push_str("router::Opt::new(");
         router_opts.push_str(&variant.to_string());
-        router_opts.push_str(",kind:router::OptArgKind::");
-        router_opts.push_str(match o.5 {
-            OptArg::Multi => "Multiple},",
-            OptArg::None => "KeyOnly},",
-            OptArg::Single => "Single},",
+        router_opts.push_str(",router::OptArgKind::");
+        router_opts.push_str(&match &o.5 {
+            OptArg::Multi => "Multiple)".to_string(),
+            OptArg::Pairs => "KeyValue)".to_string(),
+            OptArg::Optional => "Optional)".to_string(),
+            OptArg::None => "KeyOnly)".to_string(),
+            OptArg::Single => "Single)".to_string(),
+            OptArg::Tuple(types) => format!("Tuple({}))", types.len()),
         });
+        if let OptArg::Tuple(_) = o.5 {
+            if o.7.default.is_some() || o.7.env.is_some() {
+                panic!("`{}` takes a tuple, so it can't have a `default` or `env`", o.0);
+            }
+        }
+        if let OptArg::None = o.5 {
+            if o.7.default.is_some() || o.7.env.is_some() {
+                panic!("`{}` doesn't expect an option-argument, so it can't have a `default` or `env`", o.0);
+            }
+        }
+        if let Some(default) = &o.7.default {
+            router_opts.push_str(".default(");
+            router_opts.push_str(default);
+            router_opts.push(')');
+        }
+        if let Some(env) = &o.7.env {
+            router_opts.push_str(".env(");
+            router_opts.push_str(env);
+            router_opts.push(')');
+        }
+        if let Some(implicit) = &o.7.implicit {
+            if !matches!(o.5, OptArg::Optional) {
+                panic!("`{}` doesn't have an optional option-argument, so it can't have an `implicit` value", o.0);
+            }
+            router_opts.push_str(".implicit(");
+            router_opts.push_str(implicit);
+            router_opts.push(')');
+        }
+        if let Some(value_name) = &o.7.value_name {
+            router_opts.push_str(".value_name(");
+            router_opts.push_str(value_name);
+            router_opts.push(')');
+        }
+        if o.2.is_empty()
+            && (o.7.values.is_some()
+                || o.7.range.is_some()
+                || o.7.chars.is_some())
+        {
+            panic!("`{}` doesn't expect an option-argument, so it can't have `values`, a `range`, or `chars`", o.0);
+        }
+        if let Some(values) = &o.7.values {
+            router_opts.push_str(".possible_values(");
+            router_opts.push_str(values);
+            router_opts.push(')');
+        }
+        if let Some(chars) = &o.7.chars {
+            router_opts.push_str(".chars(");
+            router_opts.push_str(chars);
+            router_opts.push(')');
+        }
+        if let Some(delimiter) = &o.7.delimiter {
+            if !matches!(o.5, OptArg::Multi | OptArg::Pairs) {
+                panic!("`{}` only takes one option-argument, so it can't have a `delimiter`", o.0);
+            }
+            router_opts.push_str(".delimiter(");
+            router_opts.push_str(delimiter);
+            router_opts.push(')');
+        }
+        if let Some(repeat) = &o.7.repeat {
+            router_opts.push_str(".repeat(");
+            router_opts.push_str(repeat);
+            router_opts.push(')');
+        }
+        if let OptArg::Tuple(types) = &o.5 {
+            if o.7.range.is_some() {
+                panic!(
+                    "`{}` takes a tuple, so it can't have a `range`",
+                    o.0
+                );
+            }
+            router_opts.push_str(".element_validators(&[");
+            for t in types {
+                router_opts.push_str("router::parses::<");
+                router_opts.push_str(t);
+                router_opts.push_str(">,");
+            }
+            router_opts.push_str("])");
+
+            // Each occurrence was given every value of its tuple
+            accessors.push_str("pub fn ");
+            accessors.push_str(&accessor_name(&o.0));
+            accessors.push_str("(c:&router::Context)->Result<Vec<");
+            accessors.push_str(&o.2);
+            accessors.push_str(">,router::ParseError>{c.opt(Self::");
+            accessors.push_str(&o.0);
+            accessors.push_str(").tuples().map(|mut t|Ok((");
+            for place in 0..types.len() {
+                accessors.push_str("t.tuple_value_os(");
+                accessors.push_str(&place.to_string());
+                accessors.push_str(")?,");
+            }
+            accessors.push_str("))).collect()}");
+        } else if !o.2.is_empty() {
+            match &o.7.range {
+                // A function item coerces to a `Validator`
+                Some(range) => {
+                    router_opts.push_str(
+                        ".validator({fn v(a:&std::ffi::OsStr)->Result<(),String>{router::parses_in::<",
+                    );
+                    router_opts.push_str(&o.2);
+                    router_opts.push_str(">(a,");
+                    router_opts.push_str(range);
+                    router_opts.push_str(")}v})");
+                }
+                None => {
+                    router_opts.push_str(".validator(router::parses::<");
+                    router_opts.push_str(&o.2);
+                    router_opts.push_str(">)");
+                }
+            }
+
+            accessors.push_str("pub fn ");
+            accessors.push_str(&accessor_name(&o.0));
+            accessors.push_str(match o.5 {
+                // The keys borrow from the `Context`
+                OptArg::Pairs => "<'a>(c:&'a router::Context)->Result<",
+                _ => "(c:&router::Context)->Result<",
+            });
+            match o.5 {
+                OptArg::Multi => {
+                    accessors.push_str("Vec<");
+                    accessors.push_str(&o.2);
+                    accessors
+                        .push_str(">,router::ParseError>{c.opt(Self::");
+                    accessors.push_str(&o.0);
+                    accessors.push_str(").values_os()}");
+                }
+                OptArg::Pairs => {
+                    accessors.push_str("Vec<(&'a str,");
+                    accessors.push_str(&o.2);
+                    accessors
+                        .push_str(")>,router::ParseError>{c.opt(Self::");
+                    accessors.push_str(&o.0);
+                    accessors.push_str(").pairs_os()}");
+                }
+                _ => {
+                    accessors.push_str("Option<");
+                    accessors.push_str(&o.2);
+                    accessors
+                        .push_str(">,router::ParseError>{c.opt(Self::");
+                    accessors.push_str(&o.0);
+                    accessors.push_str(").value_os()}");
+                }
+            }
+        }
+        router_opts.push(',');
 
         summaries.push_str("\"");
         summaries.push_str(&o.1);
@@ -519,11 +990,13 @@ pub fn optmap(input: TokenStream) -> TokenStream {
         &'static[(u16,char)],
         &'static[&'static str],
         &'static[&'static str],
-        Option<u16>){(
-          &[",
+        Option<u16>){
+          // A `const` since `Opt`s are built by const fn calls,
+          // which aren't promoted to `'static` like literals
+          const OPTS:&[router::Opt]=&[",
     );
     out.push_str(&router_opts);
-    out.push_str("],&[");
+    out.push_str("];(OPTS,&[");
     out.push_str(&shorthands);
     out.push_str("],&[");
     out.push_str(&names);
@@ -538,7 +1011,9 @@ pub fn optmap(input: TokenStream) -> TokenStream {
         }
         _ => out.push_str("None"),
     }
-    out.push_str(")}}");
+    out.push_str(")}");
+    out.push_str(&accessors);
+    out.push('}');
 
     // println!("{}", out);
     // out
@@ -546,6 +1021,45 @@ pub fn optmap(input: TokenStream) -> TokenStream {
     // TokenStream::from_str("enum O {}").unwrap()
 }
 
+/// Implement `TryFrom<&router::Context>` for a struct, taking
+/// each field from an option, operands, or a path parameter
+/// by its index or name.
+///
+/// A field's type decides how it's converted: `bool` is whether
+/// an option was given, `Option<T>` may be missing, `Vec<T>`
+/// takes every value, and any other `T` is required. `T` must
+/// implement `router::FromOsStr`. When fields fail, the error
+/// has a message for each of them.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(FromContext)]
+/// struct AppArgs {
+///     #[opt(O::Quiet)]
+///     quiet: bool,
+///     #[opt(O::Width)]
+///     width: Option<u16>,
+///     #[opt(O::Number)]
+///     number: i32,
+///     #[operands]
+///     input: Vec<PathBuf>,
+///     #[operand(0)]
+///     first: Option<String>,
+///     #[path_param("id")]
+///     id: u32,
+/// }
+///
+/// let args = AppArgs::try_from(&context)?;
+/// ```
+#[proc_macro_derive(
+    FromContext,
+    attributes(opt, operands, operand, path_param)
+)]
+pub fn from_context(input: TokenStream) -> TokenStream {
+    from_context::derive(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -565,4 +1079,28 @@ mod tests {
             "some-many-words-here".to_string()
         );
     }
+    #[test]
+    fn should_name_accessors_in_snake_case() {
+        assert_eq!(accessor_name("OptNumber"), "opt_number");
+        assert_eq!(accessor_name("width"), "width");
+        assert_eq!(accessor_name("Type"), "r#type");
+    }
+    #[test]
+    fn should_read_the_value_of_a_string_literal() {
+        assert_eq!(string_literal(r#"",""#).as_deref(), Some(","));
+        assert_eq!(string_literal(r#""\t""#).as_deref(), Some("\t"));
+        assert_eq!(string_literal(r#"r",""#).as_deref(), Some(","));
+        assert_eq!(
+            string_literal(r##"r#"a"|\b"#"##).as_deref(),
+            Some(r#"a"|\b"#)
+        );
+        assert_eq!(
+            string_literal(r#""\"a\\b\x41\u{e9}""#).as_deref(),
+            Some("\"a\\bA\u{e9}")
+        );
+        assert_eq!(string_literal("\"a\\\n   b\"").as_deref(), Some("ab"));
+        assert_eq!(string_literal(r#"b",""#), None);
+        assert_eq!(string_literal("','"), None);
+        assert_eq!(string_literal("42"), None);
+    }
 }
@@ -1,6 +1,8 @@
 use {
-    crate::{Action, Context, OptGroupRules, Segment, TreeNode},
-    std::{io, mem::transmute},
+    crate::{
+        Action, Context, OptGroupRules, Segment, TreeNode, Validator,
+    },
+    std::{io, mem::transmute, ops::RangeInclusive},
 };
 
 /// Temporary struct used when flattening a Seg tree
@@ -41,6 +43,42 @@ impl OptGroup {
         self.rules |= OptGroupRules::Required as u8;
         self
     }
+    /// Apply this group to every descendant of the segment it's
+    /// declared on, unless a descendant is `isolated()`
+    pub const fn cascade(mut self) -> Self {
+        self.rules |= OptGroupRules::Cascades as u8;
+        self
+    }
+}
+
+/// A named operand of a segment, used by `Context::operand()` and
+/// shown in the segment's synopsis
+#[derive(Clone, Copy)]
+pub struct Operand {
+    pub(crate) name: &'static str,
+    pub(crate) validator: Option<Validator>,
+    pub(crate) many: bool,
+}
+impl Operand {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            validator: None,
+            many: false,
+        }
+    }
+    /// Check the operand while parsing, e.g.
+    /// `Operand::new("port").validator(router::parses::<u16>)`
+    pub const fn validator(mut self, f: Validator) -> Self {
+        self.validator = Some(f);
+        self
+    }
+    /// Take every operand that's left, like `<files>...`. Only
+    /// the last operand of a segment can
+    pub const fn many(mut self) -> Self {
+        self.many = true;
+        self
+    }
 }
 
 // const fn validate_name(name: &str) {
@@ -66,6 +104,32 @@ impl OptGroup {
 //     }
 // }
 
+/// Whether a segment is a path parameter ending in `?`
+const fn is_optional_param(name: &str) -> bool {
+    let name = name.as_bytes();
+    name.len() > 2 && name[0] == b':' && name[name.len() - 1] == b'?'
+}
+
+/// Whether two segments have the same name, ignoring whether
+/// path parameters are optional. `==` can't be used in a
+/// const fn
+const fn param_eq(a: &str, b: &str) -> bool {
+    let a_len = a.len() - is_optional_param(a) as usize;
+    let b_len = b.len() - is_optional_param(b) as usize;
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a_len != b_len {
+        return false;
+    }
+    let mut i = 0;
+    while i < a_len {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 /// Represents a segment of your API that can be an action
 /// or a nesting structure to lead to other segments. Think
 /// URLs.
@@ -77,7 +141,12 @@ pub struct Seg<'a> {
     opt_groups: &'a [OptGroup],
     action: Action,
     // doc: DocGen,
+    /// The most operands, with `min_operands` the range it takes
     operands: u16,
+    min_operands: u16,
+    validator: Option<Validator>,
+    isolated: bool,
+    named_operands: &'static [Operand],
 }
 impl<'a> Seg<'a> {
     pub const fn new(name: &'static str) -> Self {
@@ -90,6 +159,10 @@ impl<'a> Seg<'a> {
             // doc: doc::empty_doc,
             // sub_count: 0,
             operands: 0,
+            min_operands: 0,
+            validator: None,
+            isolated: false,
+            named_operands: &[],
         }
         // let mut count = 0;
         // let mut index = 0;
@@ -114,6 +187,12 @@ impl<'a> Seg<'a> {
         self.action = f;
         self
     }
+    /// Only match a path parameter when its argument passes `f`,
+    /// e.g. `Seg::new(":port").validator(router::parses::<u16>)`
+    pub const fn validator(mut self, f: Validator) -> Self {
+        self.validator = Some(f);
+        self
+    }
     /// Counts all commands in the tree, as well as their `OptGroup`s.
     pub const fn count<const DEPTH: usize>(&self) -> (usize, usize) {
         // Caches a parent and the selected child index to enable
@@ -127,6 +206,10 @@ impl<'a> Seg<'a> {
                 action: default_action,
                 // doc: doc::empty_doc,
                 operands: 0,
+                min_operands: 0,
+                validator: None,
+                isolated: false,
+                named_operands: &[],
             },
             0,
         ); DEPTH];
@@ -146,7 +229,7 @@ impl<'a> Seg<'a> {
 
                 if !child.commands.is_empty() {
                     depth += 1;
-                    breadcrumbs[depth].0 = &child;
+                    breadcrumbs[depth] = (child, 0);
                 }
                 continue;
             }
@@ -172,11 +255,13 @@ impl<'a> Seg<'a> {
         [TreeNode; COUNT],
         [Segment; COUNT],
         [Action; COUNT],
+        [Option<Validator>; COUNT],
         // [DocGen; COUNT],
         [u8; GROUP_COUNT],
         [&'static [u16]; GROUP_COUNT],
         [&'static str; STR_LIST_COUNT],
         // [&'static str; STR_LIST_COUNT],
+        [&'static [Operand]; COUNT],
     ) {
         let mut tree = [TreeNode {
             child_span: 0,
@@ -184,10 +269,14 @@ impl<'a> Seg<'a> {
         }; COUNT];
         let mut segments = [Segment {
             operands: 0,
+            min_operands: 0,
             name: 0,
             opt_groups: 0,
+            isolated: false,
         }; COUNT];
         let mut actions: [Action; COUNT] = [default_action; COUNT];
+        let mut validators: [Option<Validator>; COUNT] = [None; COUNT];
+        let mut named_operands: [&[Operand]; COUNT] = [&[]; COUNT];
         // let mut doc_gens: [DocGen; COUNT] = [doc::empty_doc; COUNT];
         let mut opt_grp_rules: [u8; GROUP_COUNT] = [0; GROUP_COUNT];
         let mut opt_grps: [&[u16]; GROUP_COUNT] = [&[]; GROUP_COUNT];
@@ -204,6 +293,10 @@ impl<'a> Seg<'a> {
                 action: default_action,
                 // doc: doc::empty_doc,
                 operands: 0,
+                min_operands: 0,
+                validator: None,
+                isolated: false,
+                named_operands: &[],
             },
             child_index: 0,
             final_index: 0,
@@ -224,6 +317,8 @@ impl<'a> Seg<'a> {
         segments[0].name = opt_names.len() as u16;
         // summaries[0 + opt_names.len()] = self.summary;
         actions[0] = self.action;
+        segments[0].isolated = self.isolated;
+        validators[0] = self.validator;
         // doc_gens[0] = self.doc;
         if !self.opt_groups.is_empty() {
             segments[0].opt_groups = (self.opt_groups.len() as u16) << 12
@@ -244,6 +339,8 @@ impl<'a> Seg<'a> {
             segments[0].operands = 0;
         } else {
             segments[0].operands = self.operands;
+            segments[0].min_operands = self.min_operands;
+            named_operands[0] = self.named_operands;
         }
         count += 1;
         breadcrumbs[0].seg = self;
@@ -256,13 +353,18 @@ impl<'a> Seg<'a> {
                 let child = breadcrumbs[depth].seg.commands
                     [breadcrumbs[depth].child_index];
 
-                // TODO: Check name for uniqueness when
-                //       strings can be compared in const fn
-
                 names[count + opt_names.len()] = child.name;
                 segments[count].name = (count + opt_names.len()) as u16;
                 // summaries[count + opt_names.len()] = child.summary;
                 actions[count] = child.action;
+                segments[count].isolated = child.isolated;
+                if child.validator.is_some()
+                    && !(child.name.len() > 1
+                        && child.name.as_bytes()[0] == b':')
+                {
+                    panic!("Only path parameters can have a validator");
+                }
+                validators[count] = child.validator;
                 // doc_gens[count] = child.doc;
                 if !child.opt_groups.is_empty() {
                     segments[count].opt_groups =
@@ -285,15 +387,44 @@ impl<'a> Seg<'a> {
                     segments[count].operands = 0;
                 } else {
                     segments[count].operands = child.operands;
+                    segments[count].min_operands = child.min_operands;
+                    named_operands[count] = child.named_operands;
                 }
                 count += 1;
                 breadcrumbs[depth].child_index += 1;
                 tree[count - 1].parent =
                     breadcrumbs[depth].final_index as u16;
 
+                if (is_optional_param(child.name)
+                    || (!child.name.is_empty()
+                        && child.name.as_bytes()[0] == b'*'))
+                    && !child.commands.is_empty()
+                {
+                    panic!("Optional path parameters and catch-all segments must end a route");
+                }
+                // A path parameter's name must be unique on its route
+                // so it can be found by name
+                if child.name.len() > 1 && child.name.as_bytes()[0] == b':'
+                {
+                    let mut ancestor = tree[count - 1].parent as usize;
+                    loop {
+                        if param_eq(
+                            names[segments[ancestor].name as usize],
+                            child.name,
+                        ) {
+                            panic!("Path parameters on the same route must have unique names");
+                        }
+                        if ancestor == 0 {
+                            break;
+                        }
+                        ancestor = tree[ancestor].parent as usize;
+                    }
+                }
+
                 if !child.commands.is_empty() {
                     depth += 1;
                     breadcrumbs[depth].seg = child;
+                    breadcrumbs[depth].child_index = 0;
                     breadcrumbs[depth].final_index = count - 1;
                 }
                 continue;
@@ -315,25 +446,63 @@ impl<'a> Seg<'a> {
             tree,
             segments,
             actions,
+            validators,
             // doc_gens,
             opt_grp_rules,
             opt_grps,
             names,
             // summaries,
+            named_operands,
         )
     }
     pub const fn nest(mut self, commands: &'a [Seg]) -> Self {
         self.commands = commands;
         self
     }
-    pub const fn operands(mut self, operands: u16) -> Self {
-        self.operands = operands;
+    /// Take a number of operands in a range, e.g. `1..=1` for
+    /// exactly one, `1..=3`, or `0..=u16::MAX` for any number. Too
+    /// few or too many are an error, regardless of
+    /// `Router::unknown_args()`
+    pub const fn operands(mut self, range: RangeInclusive<u16>) -> Self {
+        if *range.end() < *range.start() {
+            panic!("An operand range can't end before it starts");
+        }
+        self.operands = *range.end();
+        self.min_operands = *range.start();
+        self
+    }
+    /// Name the segment's operands, in order. The segment takes
+    /// exactly that many, or at least that many when the last is
+    /// `Operand::many()`, which `operands()` can change after
+    pub const fn named_operands(
+        mut self,
+        operands: &'static [Operand],
+    ) -> Self {
+        let mut i = 0;
+        while i + 1 < operands.len() {
+            if operands[i].many {
+                panic!("Only the last operand can take many");
+            }
+            i += 1;
+        }
+        self.named_operands = operands;
+        self.min_operands = operands.len() as u16;
+        self.operands = match operands.last() {
+            Some(last) if last.many => u16::MAX,
+            _ => operands.len() as u16,
+        };
         self
     }
     pub const fn options(mut self, groups: &'a [OptGroup]) -> Self {
         self.opt_groups = groups;
         self
     }
+    /// Don't inherit cascading option groups from ancestors. The
+    /// segment's descendants won't inherit them either
+    pub const fn isolated(mut self) -> Self {
+        self.isolated = true;
+        self
+    }
 }
 
 pub fn default_action(_: Context) -> io::Result<()> {
@@ -375,11 +544,13 @@ macro_rules! router {
             [router::TreeNode; _CMD_COUNT.0],
             [router::Segment; _CMD_COUNT.0],
             [router::Action; _CMD_COUNT.0],
+            [Option<router::Validator>; _CMD_COUNT.0],
             // [router::DocGen; _CMD_COUNT.0],
             [u8; _CMD_COUNT.1],
             [&[u16]; _CMD_COUNT.1],
             [&str; _STR_COUNT],
             // [&str; _STR_COUNT],
+            [&[router::Operand]; _CMD_COUNT.0],
         ) = $seg
             .flatten::<{ _CMD_COUNT.0 }, { _CMD_COUNT.1 }, _STR_COUNT>(
                 _OPS.2,
@@ -392,10 +563,12 @@ macro_rules! router {
             &_CMD_PARTS.0,
             &_CMD_PARTS.1,
             &_CMD_PARTS.2,
-            // docs: &_CMD_PARTS.3,
             &_CMD_PARTS.3,
+            &_CMD_PARTS.7,
+            // docs: &_CMD_PARTS.4,
             &_CMD_PARTS.4,
             &_CMD_PARTS.5,
+            &_CMD_PARTS.6,
             // summaries: &_CMD_PARTS.7,
             _OPS.0,
             _OPS.1,
@@ -429,6 +602,10 @@ mod tests {
             action: |_| Ok(()),
             // doc: doc::empty_doc,
             operands: 0,
+            min_operands: 0,
+            validator: None,
+            isolated: false,
+            named_operands: &[],
         };
         const CONFIG: Seg = Seg {
             name: "config",
@@ -459,6 +636,10 @@ mod tests {
                             action: |_| Ok(()),
                             // doc: doc::empty_doc,
                             operands: 0,
+                            min_operands: 0,
+                            validator: None,
+                            isolated: false,
+                            named_operands: &[],
                         },
                         Seg {
                             name: "deep2",
@@ -474,12 +655,20 @@ mod tests {
                             action: |_| Ok(()),
                             // doc: doc::empty_doc,
                             operands: 0,
+                            min_operands: 0,
+                            validator: None,
+                            isolated: false,
+                            named_operands: &[],
                         },
                     ],
                     opt_groups: &[],
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
                 Seg {
                     name: "action",
@@ -489,12 +678,20 @@ mod tests {
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
             ],
             opt_groups: &[],
             action: |_| Ok(()),
             // doc: doc::empty_doc,
             operands: 0,
+            min_operands: 0,
+            validator: None,
+            isolated: false,
+            named_operands: &[],
         };
         let (size, groups) = TEST
             .nest(&[
@@ -511,6 +708,10 @@ mod tests {
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
             ])
             .count::<16>();
@@ -527,6 +728,10 @@ mod tests {
             action: |_| Ok(()),
             // doc: doc::empty_doc,
             operands: 0,
+            min_operands: 0,
+            validator: None,
+            isolated: false,
+            named_operands: &[],
         };
         const CONFIG: Seg = Seg {
             name: "config",
@@ -557,6 +762,10 @@ mod tests {
                             action: |_| Ok(()),
                             // doc: doc::empty_doc,
                             operands: 0,
+                            min_operands: 0,
+                            validator: None,
+                            isolated: false,
+                            named_operands: &[],
                         },
                         Seg {
                             name: "deep2",
@@ -572,12 +781,20 @@ mod tests {
                             action: |_| Ok(()),
                             // doc: doc::empty_doc,
                             operands: 0,
+                            min_operands: 0,
+                            validator: None,
+                            isolated: false,
+                            named_operands: &[],
                         },
                     ],
                     opt_groups: &[],
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
                 Seg {
                     name: "action",
@@ -587,22 +804,32 @@ mod tests {
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
             ],
             opt_groups: &[],
             action: |_| Ok(()),
             // doc: doc::empty_doc,
             operands: 0,
+            min_operands: 0,
+            validator: None,
+            isolated: false,
+            named_operands: &[],
         };
         const FLATTENED_FROM_STRUCTS: (
             [TreeNode; 7],
             [Segment; 7],
             [Action; 7],
+            [Option<Validator>; 7],
             // [DocGen; 7],
             [u8; 4],
             [&[u16]; 4],
             [&str; 7],
             // [&str; 7],
+            [&[Operand]; 7],
         ) = TEST
             .nest(&[
                 CONFIG,
@@ -618,6 +845,10 @@ mod tests {
                     action: |_| Ok(()),
                     // doc: doc::empty_doc,
                     operands: 0,
+                    min_operands: 0,
+                    validator: None,
+                    isolated: false,
+                    named_operands: &[],
                 },
             ])
             .flatten::<7, 4, 7>(&[]);
@@ -657,37 +888,51 @@ mod tests {
                 Segment {
                     name: 0,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
+                    isolated: false,
                 },
                 Segment {
                     name: 1,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
+                    isolated: false,
                 },
                 Segment {
                     name: 2,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
+                    isolated: false,
                 },
                 Segment {
                     name: 3,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 2 << 12,
+                    isolated: false,
                 },
                 Segment {
                     name: 4,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 1 << 12 | 2,
+                    isolated: false,
                 },
                 Segment {
                     name: 5,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
+                    isolated: false,
                 },
                 Segment {
                     name: 6,
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 1 << 12 | 3,
+                    isolated: false,
                 },
             ],
         );
@@ -698,11 +943,13 @@ mod tests {
             [TreeNode; 7],
             [Segment; 7],
             [Action; 7],
+            [Option<Validator>; 7],
             // [DocGen; 7],
             [u8; 4],
             [&[u16]; 4],
             [&str; 7],
             // [&str; 7],
+            [&[Operand]; 7],
         ) = Seg/* ::<O> */::new("test")
             .nest(&[
                 Seg::new("config").nest(&[
@@ -785,30 +1032,76 @@ mod tests {
             OptGroupRules::AnyOf as u8,
             OptGroupRules::AnyOf as u8 | OptGroupRules::Required as u8,
         ];
-        assert_eq!(FLATTENED_FROM_STRUCTS.3, op_rules);
-        assert_eq!(FLATTENED_FROM_BUILDER.3, op_rules);
+        assert_eq!(FLATTENED_FROM_STRUCTS.4, op_rules);
+        assert_eq!(FLATTENED_FROM_BUILDER.4, op_rules);
 
-        assert_eq!(FLATTENED_FROM_STRUCTS.4[0], &[O::OptionA as u16]);
+        assert_eq!(FLATTENED_FROM_STRUCTS.5[0], &[O::OptionA as u16]);
         assert_eq!(
-            FLATTENED_FROM_STRUCTS.4[1],
+            FLATTENED_FROM_STRUCTS.5[1],
             &[O::OptionB as u16, O::OptionC as u16]
         );
         assert_eq!(
-            FLATTENED_FROM_STRUCTS.4[2],
+            FLATTENED_FROM_STRUCTS.5[2],
             &[O::OptionA as u16, O::OptionB as u16]
         );
         assert_eq!(
-            FLATTENED_FROM_STRUCTS.4[3],
+            FLATTENED_FROM_STRUCTS.5[3],
             &[O::OptionA as u16, O::OptionC as u16]
         );
     }
     #[test]
+    fn should_start_each_nested_segment_at_its_first_child() {
+        // Both nested segments are at the same depth, so `b` must
+        // not continue from where `a` left off
+        const TREE: Seg = Seg::new("prog").nest(&[
+            Seg::new("a").nest(&[Seg::new("x"), Seg::new("y")]),
+            Seg::new("b").nest(&[Seg::new("z")]),
+        ]);
+        assert_eq!(TREE.count::<3>(), (6, 0));
+        let parts = TREE.flatten::<6, 0, 6>(&[]);
+        assert_eq!(parts.6, ["prog", "a", "x", "y", "b", "z"]);
+        assert_eq!(parts.0[4].child_span, 1);
+        assert_eq!(parts.0[5].parent, 4);
+    }
+    #[test]
     fn should_set_segment_operands_to_zero_when_it_has_children() {
         let parts = Seg::new("path")
             .nest(&[Seg::new("a")
-                .operands(1)
+                .operands(1..=1)
                 .nest(&[Seg::new("a1"), Seg::new("a2")])])
             .flatten::<4, 0, 4>(&[]);
         assert_eq!(parts.1[1].operands, 0);
     }
+    #[test]
+    fn should_allow_a_path_param_name_on_separate_routes() {
+        let parts = Seg::new("repo")
+            .nest(&[
+                Seg::new("a").nest(&[Seg::new(":id")]),
+                Seg::new("b").nest(&[Seg::new(":id")]),
+            ])
+            .flatten::<5, 0, 5>(&[]);
+        assert_eq!(parts.6[2], ":id");
+        assert_eq!(parts.6[4], ":id");
+    }
+    #[test]
+    #[should_panic(expected = "unique names")]
+    fn should_reject_a_repeated_path_param_name_on_one_route() {
+        Seg::new("repo")
+            .nest(&[Seg::new(":id")
+                .nest(&[Seg::new("a").nest(&[Seg::new(":id?")])])])
+            .flatten::<4, 0, 4>(&[]);
+    }
+    #[test]
+    #[should_panic(expected = "must end a route")]
+    fn should_reject_children_of_a_catch_all() {
+        Seg::new("repo")
+            .nest(&[Seg::new("*rest").nest(&[Seg::new("a")])])
+            .flatten::<3, 0, 3>(&[]);
+    }
+    #[test]
+    #[should_panic(expected = "end before it starts")]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn should_reject_an_operand_range_that_ends_before_it_starts() {
+        let _ = Seg::new("cp").operands(3..=2);
+    }
 }
@@ -0,0 +1,85 @@
+use std::{
+    convert::Infallible,
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    path::PathBuf,
+};
+
+/// Converts an argument to a value without requiring it to be
+/// valid UTF-8, unlike `FromStr`.
+///
+/// Types that implement `FromStr` can implement this with
+/// `impl_from_os_str!()`, which is already done for the
+/// standard library's types.
+pub trait FromOsStr: Sized {
+    type Err: Display;
+
+    fn from_os_str(s: &OsStr) -> Result<Self, Self::Err>;
+}
+impl FromOsStr for OsString {
+    type Err = Infallible;
+
+    fn from_os_str(s: &OsStr) -> Result<Self, Self::Err> {
+        Ok(s.to_owned())
+    }
+}
+impl FromOsStr for PathBuf {
+    type Err = Infallible;
+
+    fn from_os_str(s: &OsStr) -> Result<Self, Self::Err> {
+        Ok(PathBuf::from(s))
+    }
+}
+
+/// Implements `FromOsStr` for types that implement `FromStr`,
+/// where the argument must be valid UTF-8.
+///
+/// ```ignore
+/// impl_from_os_str!(MyType, MyOtherType);
+/// ```
+#[macro_export]
+macro_rules! impl_from_os_str {
+    ($($t: ty),* $(,)?) => {$(
+        impl $crate::FromOsStr for $t {
+            type Err = ::std::string::String;
+
+            fn from_os_str(
+                s: &::std::ffi::OsStr,
+            ) -> ::std::result::Result<Self, Self::Err> {
+                s.to_str()
+                    .ok_or_else(|| "invalid UTF-8".to_string())?
+                    .parse::<$t>()
+                    .map_err(|e| e.to_string())
+            }
+        }
+    )*};
+}
+
+impl_from_os_str!(
+    bool,
+    char,
+    f32,
+    f64,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    String,
+    std::net::IpAddr,
+    std::net::Ipv4Addr,
+    std::net::Ipv6Addr,
+    std::net::SocketAddr,
+    std::num::NonZeroU8,
+    std::num::NonZeroU16,
+    std::num::NonZeroU32,
+    std::num::NonZeroU64,
+    std::num::NonZeroUsize,
+);
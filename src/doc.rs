@@ -1,5 +1,5 @@
 use {
-    crate::Context,
+    crate::{Context, Operand, OptArgKind, Segment, OPTION_PREFIX},
     std::{fmt::Write, ops},
 };
 
@@ -399,6 +399,40 @@ pub fn empty_doc(_: &Context, blocks: DocNodeWithoutSummary) -> DocNode {
     blocks.summary("")
 }
 
+/// How a segment's operands are shown in the synopsis, by their
+/// names when they're named
+fn operands_synopsis(segment: &Segment, named: &[Operand]) -> String {
+    let (min, max) = (segment.min_operands, segment.operands);
+    if !named.is_empty() {
+        let mut s = String::new();
+        for (i, operand) in named.iter().enumerate() {
+            let (open, close) = match i < min as usize {
+                true => ('<', '>'),
+                false => ('[', ']'),
+            };
+            s.push(' ');
+            s.push(open);
+            s.push_str(operand.name);
+            s.push(close);
+            if operand.many {
+                s.push_str("...");
+            }
+        }
+        return s;
+    }
+    match (min, max) {
+        (_, 0) => String::new(),
+        (0, 1) => " [operand]".to_string(),
+        (1, 1) => " <operand>".to_string(),
+        (0, u16::MAX) => " [operands...]".to_string(),
+        (1, u16::MAX) => " <operands...>".to_string(),
+        (min, u16::MAX) => format!(" <operands; {} or more>", min),
+        (0, max) => format!(" [operands; up to {}]", max),
+        (min, max) if min == max => format!(" <operands; {}>", max),
+        (min, max) => format!(" <operands; {} to {}>", min, max),
+    }
+}
+
 pub fn cli_doc(c: &Context) -> String {
     let mut s = String::with_capacity(0);
     let spacing = "    ";
@@ -425,40 +459,42 @@ pub fn cli_doc(c: &Context) -> String {
     if c.router.tree[c.selected as usize].child_span > 0 {
         s.push_str(" [command]");
     } else {
-        match c.router.segments[c.selected as usize].operands {
-            0 => (),
-            1 => {
-                s.push_str(" <operand>");
+        s.push_str(&operands_synopsis(
+            &c.router.segments[c.selected as usize],
+            c.router.named_operands[c.selected as usize],
+        ));
+    }
+    // A segment without groups of its own allows every option,
+    // otherwise options from groups inherited from ancestors are
+    // listed separately
+    let own_groups =
+        (c.router.segments[c.selected as usize].opt_groups >> 12) as usize;
+    let mut own = Vec::new();
+    let mut inherited = Vec::new();
+    if own_groups == 0 {
+        own.extend(0..c.router.options.len() as u16);
+    }
+    for (i, g) in c.router.segment_opt_groups(c.selected).enumerate() {
+        for &o in c.router.opt_groups[g] {
+            if own.contains(&o) || inherited.contains(&o) {
+                continue;
             }
-            u16::MAX => s.push_str(" <operands...>"),
-            n => {
-                s.push_str(" <operands; ");
-                s.push_str(&n.to_string());
-                s.push('>');
+            if i < own_groups {
+                own.push(o);
+            } else {
+                inherited.push(o);
             }
         }
     }
-    // TODO: Change this to per-command options
-    if !c.router.options.is_empty() {
-        s.push_str("\n\nOPTIONS");
-        for (i, opt) in c.router.options.iter().enumerate() {
-            s.push('\n');
-            s.push_str(spacing);
-            s.push_str("--");
-            s.push_str(c.router.names[opt.name as usize]);
-            if let Some((_, c)) = c
-                .router
-                .short_option_mappers
-                .iter()
-                .find(|(opt_index, _)| *opt_index == i as u16)
-            {
-                s.push_str(", -");
-                s.push(*c);
-            }
-            s.push_str("\t\t");
-            if !c.router.summaries[i].is_empty() {
-                s.push_str(c.router.summaries[i]);
-            }
+    for (heading, options) in
+        [("\n\nOPTIONS", own), ("\n\nINHERITED OPTIONS", inherited)]
+    {
+        if options.is_empty() {
+            continue;
+        }
+        s.push_str(heading);
+        for i in options {
+            push_option(&mut s, c, spacing, i as usize);
         }
     }
     if c.router.tree[c.selected as usize].child_span > 0 {
@@ -495,6 +531,62 @@ pub fn cli_doc(c: &Context) -> String {
     }
     s
 }
+/// Add an option's line to `cli_doc()`
+fn push_option(s: &mut String, c: &Context, spacing: &str, i: usize) {
+    let opt = &c.router.options[i];
+    s.push('\n');
+    s.push_str(spacing);
+    s.push_str(OPTION_PREFIX);
+    s.push_str(c.router.names[opt.name as usize]);
+    let value_name = opt.value_name.unwrap_or("VALUE");
+    if let OptArgKind::Optional = opt.kind {
+        s.push_str("[=");
+        s.push_str(value_name);
+        s.push(']');
+    }
+    if let Some((_, c)) = c
+        .router
+        .short_option_mappers
+        .iter()
+        .find(|(opt_index, _)| *opt_index == i as u16)
+    {
+        s.push_str(", -");
+        s.push(*c);
+    }
+    if let OptArgKind::Tuple(n) = opt.kind {
+        for _ in 0..n {
+            s.push(' ');
+            s.push_str(value_name);
+        }
+    }
+    if let Some(delimiter) = opt.delimiter {
+        s.push(' ');
+        s.push_str(value_name);
+        s.push('[');
+        s.push(delimiter);
+        s.push_str(value_name);
+        s.push_str("...]");
+    }
+    s.push_str("\t\t");
+    if !c.router.summaries[i].is_empty() {
+        s.push_str(c.router.summaries[i]);
+    }
+    if !opt.possible_values.is_empty() {
+        s.push_str(" [possible values: ");
+        s.push_str(&opt.possible_values.join(", "));
+        s.push(']');
+    }
+    if let Some(env) = opt.env {
+        s.push_str(" [env: ");
+        s.push_str(env);
+        s.push(']');
+    }
+    if let Some(default) = opt.default {
+        s.push_str(" [default: ");
+        s.push_str(default);
+        s.push(']');
+    }
+}
 pub fn default_doc_blocks(c: &Context) -> DocNodeWithoutSummary {
     let mut blocks = DocNodeWithoutSummary {
         blocks: DocNode {
@@ -681,6 +773,44 @@ fn single_bit(bitset: u8) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn should_show_operand_ranges_in_the_synopsis() {
+        let segment = |min_operands, operands| Segment {
+            operands,
+            min_operands,
+            opt_groups: 0,
+            name: 0,
+            isolated: false,
+        };
+        let synopsis = |min_operands, operands| {
+            operands_synopsis(&segment(min_operands, operands), &[])
+        };
+        assert_eq!(synopsis(0, 0), "");
+        assert_eq!(synopsis(1, 1), " <operand>");
+        assert_eq!(synopsis(2, 2), " <operands; 2>");
+        assert_eq!(synopsis(1, u16::MAX), " <operands...>");
+        assert_eq!(synopsis(0, 1), " [operand]");
+        assert_eq!(synopsis(0, u16::MAX), " [operands...]");
+        assert_eq!(synopsis(2, u16::MAX), " <operands; 2 or more>");
+        assert_eq!(synopsis(0, 3), " [operands; up to 3]");
+        assert_eq!(synopsis(1, 3), " <operands; 1 to 3>");
+
+        assert_eq!(
+            operands_synopsis(
+                &segment(2, u16::MAX),
+                &[Operand::new("dst"), Operand::new("src").many()]
+            ),
+            " <dst> <src>..."
+        );
+        assert_eq!(
+            operands_synopsis(
+                &segment(1, 2),
+                &[Operand::new("src"), Operand::new("dst")]
+            ),
+            " <src> [dst]"
+        );
+    }
+
     #[test]
     fn should_check_if_only_a_single_bitset_bit_is_set() {
         assert_eq!(single_bit(EscapeFnParams::Italic as u8), true);
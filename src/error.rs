@@ -0,0 +1,485 @@
+use std::{error::Error, ffi::OsString, fmt, io, path::PathBuf};
+
+/// The prefix of an option's full name, used when displaying
+/// an option in an error message
+#[cfg(feature = "single-hyphen-option-names")]
+pub(crate) const OPTION_PREFIX: &str = "-";
+#[cfg(not(feature = "single-hyphen-option-names"))]
+pub(crate) const OPTION_PREFIX: &str = "--";
+
+/// The reason `parse_cli_route()` failed, or an option-argument
+/// couldn't be converted.
+///
+/// A `position` is the index of the argument in the list that
+/// was parsed, which doesn't include the program name. An
+/// `option` or `segment` is the index into the `Router`'s
+/// options or segments.
+#[derive(Debug)]
+pub enum ParseError {
+    /// An option that expects an option-argument didn't get one
+    MissingArgument {
+        option: u16,
+        name: &'static str,
+        position: u16,
+    },
+    /// An occurrence of an `OptArgKind::Tuple` option that didn't
+    /// get all of its option-arguments
+    MissingArguments {
+        option: u16,
+        name: &'static str,
+        position: u16,
+        expected: u8,
+        found: u8,
+    },
+    /// An option that doesn't exist, or isn't allowed by the
+    /// option groups of the selected segment. `suggestions` are
+    /// the closest names the segment accepts
+    UnknownOption {
+        arg: OsString,
+        position: u16,
+        segment: u16,
+        suggestions: Vec<&'static str>,
+    },
+    /// An argument that doesn't match a child of the selected
+    /// segment. `suggestions` are the closest child names
+    UnknownSegment {
+        arg: OsString,
+        position: u16,
+        segment: u16,
+        suggestions: Vec<&'static str>,
+    },
+    /// An argument found after the selected segment's operands
+    /// were already filled
+    TooManyOperands {
+        arg: OsString,
+        position: u16,
+        segment: u16,
+        segment_name: &'static str,
+    },
+    /// The selected segment got fewer operands than the start of
+    /// its `Seg::operands()` range
+    MissingOperands {
+        segment: u16,
+        segment_name: &'static str,
+        min: u16,
+        found: u16,
+    },
+    /// With `Router::abbreviations()`, an argument that's a prefix
+    /// of more than one option, or of more than one child of
+    /// `segment`. `candidates` are the names it could mean
+    Ambiguous {
+        arg: OsString,
+        position: u16,
+        segment: u16,
+        candidates: Vec<&'static str>,
+    },
+    /// An option with `Repeat::Error` was given more than once
+    RepeatedOption {
+        option: u16,
+        name: &'static str,
+        position: u16,
+    },
+    /// Two options of a `OneOf` group were given
+    Conflict {
+        options: (u16, u16),
+        names: (&'static str, &'static str),
+        segment: u16,
+    },
+    /// None of the options of a `Required` group were given
+    MissingRequired {
+        options: &'static [u16],
+        names: Vec<&'static str>,
+        segment: u16,
+    },
+    /// An option given before the segment that declares it, with
+    /// `OptionPlacement::Strict`. `segment` is the one that
+    /// declares it
+    MisplacedOption {
+        option: u16,
+        name: &'static str,
+        position: u16,
+        segment: u16,
+        segment_name: &'static str,
+    },
+    /// An option with a name that isn't valid UTF-8
+    InvalidUtf8 { arg: OsString, position: u16 },
+    /// An option-argument couldn't be converted to the
+    /// requested type
+    Conversion {
+        option: u16,
+        name: &'static str,
+        arg: OsString,
+        reason: String,
+    },
+    /// An option that wasn't given got an invalid option-argument
+    /// from its environment variable `env`, or from its default
+    /// when `env` is `None`
+    InvalidFallback {
+        option: u16,
+        name: &'static str,
+        env: Option<&'static str>,
+        arg: OsString,
+        reason: String,
+    },
+    /// A path parameter couldn't be converted to the requested
+    /// type. `name` includes the leading `:`
+    InvalidPathParam {
+        name: &'static str,
+        arg: OsString,
+        reason: String,
+    },
+    /// A named operand didn't pass its validator, or couldn't be
+    /// converted to the requested type
+    InvalidOperand {
+        segment: u16,
+        name: &'static str,
+        arg: OsString,
+        reason: String,
+    },
+    /// A response file couldn't be expanded. `path` and `line` are
+    /// where the problem is, or the file that included it when it
+    /// can't be read
+    ResponseFile {
+        path: PathBuf,
+        line: Option<u32>,
+        reason: String,
+    },
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingArgument { name, .. } => write!(
+                f,
+                "Missing an option-argument for {}{}",
+                OPTION_PREFIX, name
+            ),
+            Self::MissingArguments {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "{}{} expects {} option-argument{}, but got {}",
+                OPTION_PREFIX,
+                name,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            Self::UnknownOption {
+                arg, suggestions, ..
+            } => {
+                write!(
+                    f,
+                    "Unrecognized option: {}",
+                    arg.to_string_lossy()
+                )?;
+                write_suggestions(f, OPTION_PREFIX, suggestions)
+            }
+            Self::UnknownSegment {
+                arg, suggestions, ..
+            } => {
+                write!(
+                    f,
+                    "Unrecognized command: {}",
+                    arg.to_string_lossy()
+                )?;
+                write_suggestions(f, "", suggestions)
+            }
+            Self::TooManyOperands {
+                arg, segment_name, ..
+            } => write!(
+                f,
+                "Unexpected operand for {}: {}",
+                segment_name,
+                arg.to_string_lossy()
+            ),
+            Self::MissingOperands {
+                segment_name,
+                min,
+                found,
+                ..
+            } => write!(
+                f,
+                "{} expects at least {} operand{}, but got {}",
+                segment_name,
+                min,
+                if *min == 1 { "" } else { "s" },
+                found
+            ),
+            Self::Ambiguous {
+                arg, candidates, ..
+            } => {
+                let arg = arg.to_string_lossy();
+                // Options are shown with their prefix
+                let prefix = match arg.starts_with('-') {
+                    true => OPTION_PREFIX,
+                    false => "",
+                };
+                write!(f, "{} is ambiguous: ", arg)?;
+                for (i, name) in candidates.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}{}", prefix, name)?;
+                }
+                Ok(())
+            }
+            Self::RepeatedOption { name, .. } => write!(
+                f,
+                "{}{} can only be given once",
+                OPTION_PREFIX, name
+            ),
+            Self::Conflict { names, .. } => write!(
+                f,
+                "These options are mutually exclusive: {}{}, {}{}",
+                OPTION_PREFIX, names.0, OPTION_PREFIX, names.1
+            ),
+            Self::MissingRequired { names, .. } => {
+                write!(f, "Missing a required option:")?;
+                for (i, name) in names.iter().enumerate() {
+                    write!(
+                        f,
+                        "{} {}{}",
+                        if i == 0 { "" } else { "," },
+                        OPTION_PREFIX,
+                        name
+                    )?;
+                }
+                Ok(())
+            }
+            Self::MisplacedOption {
+                name, segment_name, ..
+            } => write!(
+                f,
+                "{}{} must be given after {}",
+                OPTION_PREFIX, name, segment_name
+            ),
+            Self::InvalidUtf8 { arg, .. } => write!(
+                f,
+                "Option names must be valid UTF-8: {}",
+                arg.to_string_lossy()
+            ),
+            Self::Conversion {
+                name, arg, reason, ..
+            } => {
+                write!(
+                    f,
+                    "Invalid value \"{}\" for {}{}",
+                    arg.to_string_lossy(),
+                    OPTION_PREFIX,
+                    name
+                )?;
+                if !reason.is_empty() {
+                    write!(f, ": {}", reason)?;
+                }
+                Ok(())
+            }
+            Self::InvalidFallback {
+                name,
+                env,
+                arg,
+                reason,
+                ..
+            } => {
+                match env {
+                    Some(env) => write!(
+                        f,
+                        "Invalid value \"{}\" for {}{} from the environment variable {}",
+                        arg.to_string_lossy(),
+                        OPTION_PREFIX,
+                        name,
+                        env
+                    )?,
+                    None => write!(
+                        f,
+                        "Invalid default value \"{}\" for {}{}",
+                        arg.to_string_lossy(),
+                        OPTION_PREFIX,
+                        name
+                    )?,
+                }
+                if !reason.is_empty() {
+                    write!(f, ": {}", reason)?;
+                }
+                Ok(())
+            }
+            Self::InvalidPathParam { name, arg, reason } => {
+                write!(
+                    f,
+                    "Invalid value \"{}\" for {}",
+                    arg.to_string_lossy(),
+                    name
+                )?;
+                if !reason.is_empty() {
+                    write!(f, ": {}", reason)?;
+                }
+                Ok(())
+            }
+            Self::InvalidOperand {
+                name, arg, reason, ..
+            } => {
+                write!(
+                    f,
+                    "Invalid value \"{}\" for <{}>",
+                    arg.to_string_lossy(),
+                    name
+                )?;
+                if !reason.is_empty() {
+                    write!(f, ": {}", reason)?;
+                }
+                Ok(())
+            }
+            Self::ResponseFile { path, line, reason } => {
+                write!(f, "{}", path.display())?;
+                if let Some(line) = line {
+                    write!(f, ":{}", line)?;
+                }
+                write!(f, ": {}", reason)
+            }
+        }
+    }
+}
+impl Error for ParseError {}
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> Self {
+        io::Error::new(
+            match e {
+                ParseError::Conversion { .. }
+                | ParseError::InvalidFallback { .. }
+                | ParseError::InvalidPathParam { .. }
+                | ParseError::InvalidOperand { .. }
+                | ParseError::InvalidUtf8 { .. } => {
+                    io::ErrorKind::InvalidData
+                }
+                _ => io::ErrorKind::InvalidInput,
+            },
+            e,
+        )
+    }
+}
+
+/// A field of a `#[derive(FromContext)]` struct that couldn't
+/// be taken from a `Context`
+#[derive(Debug)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+/// Every field of a `#[derive(FromContext)]` struct that
+/// couldn't be taken from a `Context`
+#[derive(Debug)]
+pub struct FieldErrors(pub Vec<FieldError>);
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: {}", e.field, e.message)?;
+        }
+        Ok(())
+    }
+}
+impl Error for FieldErrors {}
+impl From<FieldErrors> for io::Error {
+    fn from(e: FieldErrors) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+fn write_suggestions(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    suggestions: &[&str],
+) -> fmt::Result {
+    for (i, name) in suggestions.iter().enumerate() {
+        write!(
+            f,
+            "{}{}{}",
+            if i == 0 { "\n  Did you mean " } else { ", " },
+            prefix,
+            name
+        )?;
+    }
+    if !suggestions.is_empty() {
+        write!(f, "?")?;
+    }
+    Ok(())
+}
+
+/// The names within a small edit distance of `name`, closest
+/// first. At most 3 are returned.
+pub(crate) fn closest_names(
+    name: &str,
+    candidates: impl Iterator<Item = &'static str>,
+) -> Vec<&'static str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut found = candidates
+        .filter_map(|c| {
+            let d = edit_distance(name, c);
+            (d <= max_distance).then_some((d, c))
+        })
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+    found.dedup();
+    found.into_iter().take(3).map(|(_, c)| c).collect()
+}
+
+/// The number of insertions, deletions, substitutions, and
+/// swaps of adjacent characters needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Three rows of the distance matrix are enough to also
+    // count swaps
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut row = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cost = (a[i - 1] != b[j - 1]) as usize;
+            row[j] =
+                (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1
+                && j > 1
+                && a[i - 1] == b[j - 2]
+                && a[i - 2] == b[j - 1]
+            {
+                row[j] = row[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut row);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_count_edits_between_names() {
+        assert_eq!(edit_distance("verbose", "verbose"), 0);
+        assert_eq!(edit_distance("verbos", "verbose"), 1);
+        assert_eq!(edit_distance("stauts", "status"), 1);
+        assert_eq!(edit_distance("", "add"), 3);
+        assert_eq!(edit_distance("remove", "rm"), 4);
+    }
+    #[test]
+    fn should_find_the_closest_names() {
+        let names = ["verbose", "version", "quiet", "verb"];
+        assert_eq!(
+            closest_names("verbos", names.into_iter()),
+            vec!["verbose", "verb"]
+        );
+        assert_eq!(
+            closest_names("versoin", names.into_iter()),
+            vec!["version"]
+        );
+        assert!(closest_names("xyz", names.into_iter()).is_empty());
+    }
+}
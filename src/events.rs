@@ -0,0 +1,120 @@
+use {
+    crate::{Context, OptArgKind},
+    std::ffi::OsString,
+};
+
+/// An item of the command line, in the order it was given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A segment matched by its name
+    Segment(u16),
+    /// A path parameter's segment, and the argument it matched
+    PathParam {
+        segment: u16,
+        value: &'a OsString,
+    },
+    /// An option, and its option-arg when it was given one. For
+    /// a delimited or tuple option, it's the first of the
+    /// occurrence
+    Option {
+        option: u16,
+        value: Option<&'a OsString>,
+    },
+    Operand(&'a OsString),
+    /// The `--` argument. Operands after it are terminated args
+    Terminator,
+}
+
+/// What's recorded while parsing. Operands aren't recorded,
+/// since they're kept in order, and are put between the other
+/// events by the number of operands found before each one
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RawEvent {
+    Segment(u16),
+    /// An option, and an index into `Context::event_args` for its
+    /// option-arg
+    Option(u16, Option<u16>),
+    Terminator,
+}
+
+impl Context<'_> {
+    /// The segments, path parameters, options, operands, and
+    /// terminator in the order they were given, for programs
+    /// where the order matters. Option-args from the environment
+    /// or a default aren't included.
+    ///
+    /// The `Router` must record them with `record_events()`,
+    /// otherwise there are only operands and terminated args.
+    pub fn events(&self) -> Vec<Event<'_>> {
+        let mut events =
+            Vec::with_capacity(self.events.len() + self.operands.len());
+        // Path parameters are only known by their segment's event
+        let mut operand = match self.router.record_events {
+            true => 0,
+            false => self.path_params as usize,
+        };
+        for &(raw, operands_before) in self.events.iter() {
+            let operands_before = operands_before as usize;
+            events.extend(
+                self.operands[operand..operands_before]
+                    .iter()
+                    .map(Event::Operand),
+            );
+            operand = operands_before;
+            events.push(match raw {
+                RawEvent::Segment(segment) => {
+                    let name = self.router.names[self.router.segments
+                        [segment as usize]
+                        .name
+                        as usize];
+                    // A path parameter's argument is the next operand
+                    if name.starts_with(':')
+                        && operand < self.operands.len()
+                    {
+                        operand += 1;
+                        Event::PathParam {
+                            segment,
+                            value: &self.operands[operand - 1],
+                        }
+                    } else {
+                        Event::Segment(segment)
+                    }
+                }
+                RawEvent::Option(option, value) => Event::Option {
+                    option,
+                    value: value.map(|v| &self.event_args[v as usize]),
+                },
+                RawEvent::Terminator => Event::Terminator,
+            });
+        }
+        events.extend(self.operands[operand..].iter().map(Event::Operand));
+        events
+    }
+}
+
+/// Record an option that was given, with the first of the
+/// `added` values that were just added for it
+pub(crate) fn record_option(c: &mut Context, option: usize, added: usize) {
+    if !c.router.record_events {
+        return;
+    }
+    let args = c.opt(option).args;
+    let value =
+        match (&c.router.options[option].kind, added.min(args.len())) {
+            // Without an option-arg or an implicit one, there's none
+            (OptArgKind::KeyOnly, _) | (_, 0) => None,
+            (_, added) => {
+                c.event_args.push(args[args.len() - added].clone());
+                Some(c.event_args.len() as u16 - 1)
+            }
+        };
+    record(c, RawEvent::Option(option as u16, value));
+}
+
+/// Record an event, when the `Router` records them
+#[inline]
+pub(crate) fn record(c: &mut Context, event: RawEvent) {
+    if c.router.record_events {
+        c.events.push((event, c.operands.len() as u16));
+    }
+}
@@ -0,0 +1,257 @@
+use {crate::OPTION_PREFIX, std::ffi::OsString};
+
+/// An argument of the command line, as read by `Lexer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// An option given by its full name, e.g. `--width`, or
+    /// `-width` with the single-hyphen-option-names feature
+    Long(LongOpt),
+    /// One or more short options grouped together, e.g. `-vq`
+    Shorts(ShortOpts),
+    /// The `--` argument. Every argument after it is a `Value`
+    Terminator,
+    /// The `-` argument, which by convention means stdin
+    Stdin,
+    /// Any other argument: a segment name, a path parameter,
+    /// or an operand
+    Value(OsString),
+    /// An argument starting with '-' that isn't valid UTF-8, so
+    /// it can't be an option
+    Raw(OsString),
+}
+impl Token {
+    /// The argument this token was read from
+    pub fn into_arg(self) -> OsString {
+        match self {
+            Token::Long(long) => long.arg.into(),
+            Token::Shorts(shorts) => shorts.arg.into(),
+            Token::Terminator => "--".into(),
+            Token::Stdin => "-".into(),
+            Token::Value(arg) | Token::Raw(arg) => arg,
+        }
+    }
+}
+
+/// An option given by its full name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongOpt {
+    arg: String,
+    /// Where the '=' separating an option-arg is, with the
+    /// eq-separator feature
+    eq: Option<usize>,
+}
+impl LongOpt {
+    fn new(arg: String) -> Self {
+        #[cfg(feature = "eq-separator")]
+        let eq = arg.find('=');
+        #[cfg(not(feature = "eq-separator"))]
+        let eq = None;
+        Self { arg, eq }
+    }
+    /// The option's name, without its prefix
+    pub fn name(&self) -> &str {
+        &self.arg[OPTION_PREFIX.len()..self.eq.unwrap_or(self.arg.len())]
+    }
+    /// The option-arg given with a '=', e.g. `--width=80`, with
+    /// the eq-separator feature
+    pub fn value(&self) -> Option<&str> {
+        self.eq.map(|eq| &self.arg[eq + 1..])
+    }
+    pub fn as_str(&self) -> &str {
+        &self.arg
+    }
+    /// With single-hyphen-option-names, the same argument read as
+    /// short options, for when no option has its name
+    #[cfg(feature = "single-hyphen-option-names")]
+    pub fn shorts(&self) -> Option<ShortOpts> {
+        match self.arg.starts_with("--") {
+            true => None,
+            false => Some(ShortOpts {
+                arg: self.arg.clone(),
+            }),
+        }
+    }
+}
+
+/// Short options grouped together in one argument, e.g. `-vq`.
+///
+/// Like getopt, the first one that expects an option-arg takes
+/// the rest of the group as its value, e.g. `-ofile.txt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortOpts {
+    arg: String,
+}
+impl ShortOpts {
+    /// Each character after the hyphen, with its index
+    pub fn chars(&self) -> std::str::CharIndices<'_> {
+        self.arg[1..].char_indices()
+    }
+    /// What follows the character at `index` from `chars()`, as an
+    /// option-arg. With the eq-separator feature, a '=' before it
+    /// is skipped, e.g. `-o=file.txt`. None for an index that isn't
+    /// one of theirs
+    pub fn attached(&self, index: usize) -> Option<&str> {
+        let rest = self.arg.get(1 + index..)?;
+        let rest = &rest[rest.chars().next()?.len_utf8()..];
+        if rest.is_empty() {
+            return None;
+        }
+        #[cfg(feature = "eq-separator")]
+        let rest = rest.strip_prefix('=').unwrap_or(rest);
+        Some(rest)
+    }
+    pub fn as_str(&self) -> &str {
+        &self.arg
+    }
+}
+
+/// Reads arguments as the `Token`s `parse_cli_route()` uses, for
+/// tools that need the same grammar, like wrappers or completion.
+///
+/// Whether an option takes the next argument as its option-arg
+/// depends on the option, so it's taken with `value()`
+///
+/// ```
+/// use {router::{Lexer, Token}, std::ffi::OsString};
+///
+/// let args = ["file.txt", "-", "--", "-q"].map(OsString::from);
+/// let mut lexer = Lexer::new(args);
+/// assert_eq!(lexer.next(), Some(Token::Value("file.txt".into())));
+/// assert_eq!(lexer.next(), Some(Token::Stdin));
+/// assert_eq!(lexer.position(), 1);
+/// assert_eq!(lexer.next(), Some(Token::Terminator));
+/// assert_eq!(lexer.next(), Some(Token::Value("-q".into())));
+/// ```
+pub struct Lexer<I> {
+    args: I,
+    /// How many arguments were taken
+    taken: usize,
+    terminated: bool,
+}
+impl<I: Iterator<Item = OsString>> Lexer<I> {
+    pub fn new(args: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            args: args.into_iter(),
+            taken: 0,
+            terminated: false,
+        }
+    }
+    /// The position of the argument last taken
+    pub fn position(&self) -> usize {
+        self.taken.saturating_sub(1)
+    }
+    /// Take the next argument as it is, as an option-arg
+    pub fn value(&mut self) -> Option<OsString> {
+        let arg = self.args.next()?;
+        self.taken += 1;
+        Some(arg)
+    }
+}
+impl<I: Iterator<Item = OsString>> Iterator for Lexer<I> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let arg = self.value()?;
+        if self.terminated {
+            return Some(Token::Value(arg));
+        }
+        let arg = match arg.into_string() {
+            Ok(arg) => arg,
+            // Options are all UTF-8
+            Err(arg) => {
+                return Some(match arg.as_encoded_bytes()[0] {
+                    b'-' => Token::Raw(arg),
+                    _ => Token::Value(arg),
+                })
+            }
+        };
+        Some(match arg.as_bytes() {
+            b"-" => Token::Stdin,
+            b"--" => {
+                self.terminated = true;
+                Token::Terminator
+            }
+            #[cfg(not(feature = "single-hyphen-option-names"))]
+            [b'-', b'-', ..] => Token::Long(LongOpt::new(arg)),
+            #[cfg(not(feature = "single-hyphen-option-names"))]
+            [b'-', ..] => Token::Shorts(ShortOpts { arg }),
+            #[cfg(feature = "single-hyphen-option-names")]
+            [b'-', ..] => Token::Long(LongOpt::new(arg)),
+            _ => Token::Value(arg.into()),
+        })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.args.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(args: &[&str]) -> Vec<Token> {
+        Lexer::new(args.iter().map(OsString::from)).collect()
+    }
+
+    #[test]
+    fn should_read_values_stdin_and_terminated_args() {
+        assert_eq!(
+            lex(&["sub", "-", "--", "--verbose", "-"]),
+            [
+                Token::Value("sub".into()),
+                Token::Stdin,
+                Token::Terminator,
+                Token::Value("--verbose".into()),
+                Token::Value("-".into()),
+            ]
+        );
+    }
+    #[test]
+    fn should_read_long_options_and_their_attached_values() {
+        let arg = format!("{}width=80", OPTION_PREFIX);
+        let long = match &lex(&[&arg])[..] {
+            [Token::Long(long)] => long.clone(),
+            t => panic!("Expected a long option, got {:?}", t),
+        };
+        #[cfg(feature = "eq-separator")]
+        assert_eq!((long.name(), long.value()), ("width", Some("80")));
+        #[cfg(not(feature = "eq-separator"))]
+        assert_eq!((long.name(), long.value()), ("width=80", None));
+        assert_eq!(Token::Long(long).into_arg(), OsString::from(arg));
+    }
+    #[cfg(not(feature = "single-hyphen-option-names"))]
+    #[test]
+    fn should_read_short_option_clusters() {
+        let shorts = match &lex(&["-vo=out"])[..] {
+            [Token::Shorts(shorts)] => shorts.clone(),
+            t => panic!("Expected short options, got {:?}", t),
+        };
+        assert_eq!(
+            shorts.chars().map(|(_, ch)| ch).collect::<String>(),
+            "vo=out"
+        );
+        assert_eq!(shorts.attached(0), Some("o=out"));
+        #[cfg(feature = "eq-separator")]
+        assert_eq!(shorts.attached(1), Some("out"));
+        #[cfg(not(feature = "eq-separator"))]
+        assert_eq!(shorts.attached(1), Some("=out"));
+        assert_eq!(shorts.attached(5), None);
+        // * An index that isn't from `chars()` isn't a panic
+        assert_eq!(shorts.attached(6), None);
+        assert_eq!(shorts.attached(usize::MAX - 1), None);
+        let shorts = match &lex(&["-éx"])[..] {
+            [Token::Shorts(shorts)] => shorts.clone(),
+            t => panic!("Expected short options, got {:?}", t),
+        };
+        assert_eq!(shorts.attached(0), Some("x"));
+        assert_eq!(shorts.attached(1), None);
+    }
+    #[test]
+    fn should_take_the_next_argument_as_a_value() {
+        let mut lexer = Lexer::new(["-o", "--", "x"].map(OsString::from));
+        assert!(lexer.next().is_some());
+        assert_eq!(lexer.value(), Some("--".into()));
+        assert_eq!(lexer.next(), Some(Token::Value("x".into())));
+        assert_eq!(lexer.position(), 2);
+    }
+}
@@ -7,18 +7,43 @@
 //!   instead of "--", and short options are disabled
 
 mod builder;
+mod convert;
 mod doc;
+mod error;
+mod events;
+mod lexer;
+mod response_file;
 mod slim;
 // mod uri;
-use std::{ffi::OsString, io, ops::Range, str::FromStr};
-pub use {builder::*, doc::*, opt_map::optmap};
+// Lets `optmap!()` output, which refers to `router::`, be used in
+// this crate's tests
+extern crate self as router;
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    io,
+    ops::{Bound, Range, RangeBounds},
+    path::PathBuf,
+    str::FromStr,
+};
+pub use {
+    builder::*,
+    convert::*,
+    doc::*,
+    error::*,
+    events::Event,
+    lexer::*,
+    opt_map::{optmap, FromContext},
+};
 
 pub type Action = fn(c: Context) -> io::Result<()>;
 
 /// An option-argument, i.e. the option's value(s)
 pub struct Arg<'a> {
     context: &'a Context<'a>,
-    range: Range<u16>,
+    option: u16,
+    /// The values that haven't been taken yet
+    args: &'a [OsString],
 }
 impl<'a> Arg<'a> {
     // Decision:
@@ -35,20 +60,130 @@ impl<'a> Arg<'a> {
     ///
     /// A key-only option, i.e. it doesn't expect option-
     /// arguments, returns `Ok(None)`.
-    pub fn value<T: FromStr>(&mut self) -> io::Result<Option<T>> {
-        if self.range.is_empty() {
+    pub fn value<T: FromStr>(&mut self) -> Result<Option<T>, ParseError>
+    where
+        T::Err: Display,
+    {
+        let Some((arg, rest)) = self.args.split_first() else {
             return Ok(None);
+        };
+        self.args = rest;
+        match arg.to_str() {
+            None => Err(self.conversion_error(arg, "invalid UTF-8")),
+            Some(a) => a
+                .parse::<T>()
+                .map(Some)
+                .map_err(|e| self.conversion_error(arg, e)),
         }
-        self.range.start += 1;
-
-        match self.context.saved_args[self.range.start as usize - 1]
-            .to_str()
-        {
-            None => Err(io::Error::new(io::ErrorKind::InvalidData, "")),
-            Some(a) => match a.parse::<T>() {
-                Ok(v) => Ok(Some(v)),
-                _ => Err(io::Error::new(io::ErrorKind::InvalidData, "")),
+    }
+    /// Like `value()`, except the argument doesn't need to be
+    /// valid UTF-8 unless `T` requires it
+    pub fn value_os<T: FromOsStr>(
+        &mut self,
+    ) -> Result<Option<T>, ParseError> {
+        let Some((arg, rest)) = self.args.split_first() else {
+            return Ok(None);
+        };
+        self.args = rest;
+        T::from_os_str(arg)
+            .map(Some)
+            .map_err(|e| self.conversion_error(arg, e))
+    }
+    /// Like `value_os()`, for the value at `place` in an occurrence
+    /// of an `OptArgKind::Tuple` option from `tuples()`. Without
+    /// it, the occurrence is short of values
+    pub fn tuple_value_os<T: FromOsStr>(
+        &mut self,
+        place: u8,
+    ) -> Result<T, ParseError> {
+        let router = self.context.router;
+        let opt = &router.options[self.option as usize];
+        self.value_os()?.ok_or(ParseError::MissingArguments {
+            option: self.option,
+            name: router.names[opt.name as usize],
+            position: 0,
+            expected: match opt.kind {
+                OptArgKind::Tuple(n) => n,
+                _ => 1,
             },
+            found: place,
+        })
+    }
+    /// Convert all of the remaining option-arguments with
+    /// `value_os()`
+    pub fn values_os<T: FromOsStr>(
+        &mut self,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut values = Vec::with_capacity(self.args.len());
+        while let Some(v) = self.value_os()? {
+            values.push(v);
+        }
+        Ok(values)
+    }
+    /// Like `value_os()`, for a `KEY=VALUE` option-argument of
+    /// an `OptArgKind::KeyValue` option, where only the value
+    /// is converted to `T`
+    pub fn pair_os<T: FromOsStr>(
+        &mut self,
+    ) -> Result<Option<(&'a str, T)>, ParseError> {
+        let Some((arg, rest)) = self.args.split_first() else {
+            return Ok(None);
+        };
+        self.args = rest;
+        let (key, value) = split_pair(arg).ok_or_else(|| {
+            self.conversion_error(arg, "Expected KEY=VALUE")
+        })?;
+        T::from_os_str(value)
+            .map(|v| Some((key, v)))
+            .map_err(|e| self.conversion_error(arg, e))
+    }
+    /// Convert all of the remaining option-arguments with
+    /// `pair_os()`, in the order they were given
+    pub fn pairs_os<T: FromOsStr>(
+        &mut self,
+    ) -> Result<Vec<(&'a str, T)>, ParseError> {
+        let mut pairs = Vec::with_capacity(self.args.len());
+        while let Some(pair) = self.pair_os()? {
+            pairs.push(pair);
+        }
+        Ok(pairs)
+    }
+    /// Split the remaining option-arguments of an
+    /// `OptArgKind::Tuple` option into those of each occurrence,
+    /// in the order they were given
+    pub fn tuples(&mut self) -> impl Iterator<Item = Arg<'a>> + 'a {
+        let size =
+            match self.context.router.options[self.option as usize].kind {
+                OptArgKind::Tuple(n) => n as usize,
+                _ => 1,
+            };
+        let (context, option) = (self.context, self.option);
+        std::mem::take(&mut self.args)
+            .chunks(size)
+            .map(move |args| Arg {
+                context,
+                option,
+                args,
+            })
+    }
+    /// Get the next option-argument as a path, which doesn't
+    /// need to be valid UTF-8
+    #[inline]
+    pub fn path(&mut self) -> Result<Option<PathBuf>, ParseError> {
+        self.value_os()
+    }
+    fn conversion_error(
+        &self,
+        arg: &OsString,
+        reason: impl Display,
+    ) -> ParseError {
+        let router = self.context.router;
+        ParseError::Conversion {
+            option: self.option,
+            name: router.names
+                [router.options[self.option as usize].name as usize],
+            arg: arg.clone(),
+            reason: reason.to_string(),
         }
     }
 }
@@ -70,12 +205,26 @@ pub struct Context<'a> {
     // should appear next to each other in `saved_args`
     arg_ranges: Vec<Range<u16>>,
     // How many times the option was found
-    option_occurrences: Vec<u8>,
+    option_occurrences: Vec<u16>,
     pub router: &'a Router,
     pub selected: u16,
     /// Where operands end and args after a terminator begin
     operands_end: u16,
     path_params: u8,
+    /// Arguments that weren't recognized, when the `Router`
+    /// collects them
+    unknown_args: Vec<OsString>,
+    /// The option, selected segment, position, and number of
+    /// option-args of each option given, when the `Router` records
+    /// them. Sorted by option, then segment, after parsing
+    placements: Vec<(u16, u16, u16, u16)>,
+    /// The option-args of each of `placements`, in the same order
+    placed_args: Vec<OsString>,
+    /// The parsed items besides operands, with the number of
+    /// operands before each, when the `Router` records them
+    events: Vec<(events::RawEvent, u16)>,
+    /// The option-args of `events`
+    event_args: Vec<OsString>,
 }
 impl<'a> Context<'a> {
     #[inline]
@@ -83,57 +232,274 @@ impl<'a> Context<'a> {
         &self.operands
             [self.path_params as usize..self.operands_end as usize]
     }
-    /// Return an iterater-like to get an option's value(s)
+    /// Return an iterater-like to get an option's value(s).
+    ///
+    /// When the option wasn't given, the values come from its
+    /// environment variable or default, if it has them.
     pub fn opt(&self, option: impl Into<usize> + Copy) -> Arg {
         let mut arg = Arg {
             context: self,
-            range: 0..0,
+            option: option.into() as u16,
+            args: &[],
         };
-        if self.option_occurrences[option.into()] == 0 {
-            return arg;
-        }
         if let OptArgKind::KeyOnly =
             self.router.options[option.into()].kind
         {
             return arg;
         }
-        arg.range.start = self
+        // Options that weren't given can still have a value from
+        // the environment or a default
+        let start = match self
             .option_args
             .iter()
             .find(|(o, _)| *o as usize == option.into())
-            // Unwrap should be safe because non-KeyOnly options have values,
-            // and their occurrence was already checked
-            .unwrap()
-            .1;
-        match self.router.options[option.into()].kind {
-            OptArgKind::Multiple => {
-                arg.range.end =
-                    self.arg_ranges[arg.range.start as usize].end;
-                arg.range.start =
-                    self.arg_ranges[arg.range.start as usize].start;
-            }
-            _ => {
-                arg.range.end = arg.range.start + 1;
+        {
+            Some((_, start)) => *start as usize,
+            _ => return arg,
+        };
+        arg.args = match self.router.options[option.into()].kind.is_list()
+        {
+            true => {
+                let range = &self.arg_ranges[start];
+                &self.saved_args[range.start as usize..range.end as usize]
             }
+            false => &self.saved_args[start..start + 1],
         };
-
         arg
     }
+    /// Like `opt()`, but only with the values given while
+    /// `segment` was the selected segment, i.e. after its name
+    /// and before the next segment's name. Environment and
+    /// default values aren't included.
+    ///
+    /// The `Router` must record where options are given with
+    /// `OptionPlacement::Record` or `OptionPlacement::Strict`,
+    /// otherwise there are no values.
+    pub fn opt_at(
+        &self,
+        segment: u16,
+        option: impl Into<usize> + Copy,
+    ) -> Arg<'_> {
+        let range = self.placement_range(segment, option.into() as u16);
+        let count = |placements: &[(u16, u16, u16, u16)]| {
+            placements.iter().map(|p| p.3 as usize).sum::<usize>()
+        };
+        let start = count(&self.placements[..range.start]);
+        Arg {
+            context: self,
+            option: option.into() as u16,
+            args: &self.placed_args
+                [start..start + count(&self.placements[range])],
+        }
+    }
+    /// How many times an option was given while `segment` was
+    /// the selected segment. Like `opt_at()`, the `Router` must
+    /// record where options are given.
+    pub fn option_occurrences_at(
+        &self,
+        segment: u16,
+        option: impl Into<usize> + Copy,
+    ) -> usize {
+        self.placement_range(segment, option.into() as u16).len()
+    }
+    /// The range of `placements` for an option at a segment,
+    /// which are sorted by option, then segment
+    fn placement_range(&self, segment: u16, option: u16) -> Range<usize> {
+        let start = self
+            .placements
+            .partition_point(|&(o, s, ..)| (o, s) < (option, segment));
+        let end = self
+            .placements
+            .partition_point(|&(o, s, ..)| (o, s) <= (option, segment));
+        start..end
+    }
+    /// How many times an option was given, up to `u16::MAX`, not
+    /// counting occurrences its `Repeat` policy ignored
     #[inline]
     pub fn option_occurrences(
         &self,
         option: impl Into<usize> + Copy,
-    ) -> u8 {
+    ) -> u16 {
         self.option_occurrences[option.into()]
     }
+    /// A verbosity level from counted flags, like `-vv` for 2, or
+    /// `-q` for -1
+    pub fn verbosity(
+        &self,
+        louder: impl Into<usize> + Copy,
+        quieter: impl Into<usize> + Copy,
+    ) -> i32 {
+        self.option_occurrences(louder) as i32
+            - self.option_occurrences(quieter) as i32
+    }
     #[inline]
     pub fn path_params(&self) -> &[OsString] {
         &self.operands[..self.path_params as usize]
     }
+    /// The value of a path parameter on the selected route, by
+    /// its name with or without the leading `:`
+    pub fn path_param(&self, name: &str) -> Option<&OsString> {
+        self.find_path_param(name)
+            .map(|(_, index)| &self.operands[index])
+    }
+    /// The value of a path parameter converted to `T`
+    pub fn path_param_value<T: FromOsStr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, ParseError> {
+        let Some((name, index)) = self.find_path_param(name) else {
+            return Ok(None);
+        };
+        let arg = &self.operands[index];
+        T::from_os_str(arg).map(Some).map_err(|e| {
+            ParseError::InvalidPathParam {
+                name,
+                arg: arg.clone(),
+                reason: e.to_string(),
+            }
+        })
+    }
+    /// An operand by the name given with `Seg::named_operands()`,
+    /// or the first one of a `many()` operand
+    pub fn operand(&self, name: &str) -> Option<&OsString> {
+        self.named_operand(name).first()
+    }
+    /// The operands taken by a named operand, which are all of the
+    /// rest for a `many()` operand
+    pub fn named_operand(&self, name: &str) -> &[OsString] {
+        let named = self.router.named_operands[self.selected as usize];
+        let operands = self.operands();
+        match named.iter().position(|o| o.name == name) {
+            Some(i) if named[i].many => operands.get(i..),
+            Some(i) => operands.get(i..i + 1),
+            None => None,
+        }
+        .unwrap_or(&[])
+    }
+    /// The value of a named operand converted to `T`
+    pub fn operand_value<T: FromOsStr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, ParseError> {
+        let named = self.router.named_operands[self.selected as usize];
+        let (Some(operand), Some(arg)) =
+            (named.iter().find(|o| o.name == name), self.operand(name))
+        else {
+            return Ok(None);
+        };
+        T::from_os_str(arg).map(Some).map_err(|e| {
+            ParseError::InvalidOperand {
+                segment: self.selected,
+                name: operand.name,
+                arg: arg.clone(),
+                reason: e.to_string(),
+            }
+        })
+    }
+    /// Arguments captured by a catch-all segment, like `*rest`,
+    /// when it's selected. They're everything after the segment
+    /// before it, including options, so they can be forwarded
+    pub fn rest(&self) -> &[OsString] {
+        let name = self.router.names
+            [self.router.segments[self.selected as usize].name as usize];
+        if name.starts_with('*') {
+            &self.operands[self.path_params as usize..]
+        } else {
+            &[]
+        }
+    }
+    /// The full name and operand index of a path parameter, found
+    /// by walking from the selected segment up to the root
+    fn find_path_param(
+        &self,
+        name: &str,
+    ) -> Option<(&'static str, usize)> {
+        let name = name.strip_prefix(':').unwrap_or(name);
+        let route = std::iter::successors(Some(self.selected), |&s| {
+            (s != 0).then(|| self.router.tree[s as usize].parent)
+        })
+        .map(|s| {
+            self.router.names
+                [self.router.segments[s as usize].name as usize]
+        });
+        // An optional path parameter can be selected without a
+        // value, and it can only be the selected segment
+        let mut unset =
+            route.clone().filter(|n| n.starts_with(':')).count()
+                > self.path_params as usize;
+        let mut index = self.path_params as usize;
+        for seg_name in route {
+            let Some(param) = seg_name.strip_prefix(':') else {
+                continue;
+            };
+            if unset {
+                unset = false;
+                continue;
+            }
+            index -= 1;
+            if param.trim_end_matches('?') == name {
+                return Some((seg_name, index));
+            }
+        }
+        None
+    }
     #[inline]
     pub fn terminated_args(&self) -> &[OsString] {
         &self.operands[self.operands_end as usize..]
     }
+    /// Arguments that didn't match an option or segment, in the
+    /// order they were given. Only filled when the `Router` is
+    /// set to `UnknownArgs::Collect`, so an action can forward
+    /// them to another program.
+    #[inline]
+    pub fn unknown_args(&self) -> &[OsString] {
+        &self.unknown_args
+    }
+}
+
+/// Checks that an option-argument can be converted to an
+/// option's type, giving the reason when it can't
+pub type Validator = fn(&OsStr) -> Result<(), String>;
+
+/// A `Validator` for any `FromOsStr` type
+pub fn parses<T: FromOsStr>(arg: &OsStr) -> Result<(), String> {
+    T::from_os_str(arg).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Like `parses()`, where the value must also be in `range`. To
+/// use it as a `Validator`, wrap it in a function
+///
+/// ```ignore
+/// fn jobs(arg: &OsStr) -> Result<(), String> {
+///     router::parses_in::<u16>(arg, 1..=256)
+/// }
+/// ```
+pub fn parses_in<T: FromOsStr + PartialOrd + Display>(
+    arg: &OsStr,
+    range: impl RangeBounds<T>,
+) -> Result<(), String> {
+    let value = T::from_os_str(arg).map_err(|e| e.to_string())?;
+    if range.contains(&value) {
+        return Ok(());
+    }
+    Err(match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(a), Bound::Included(b)) => {
+            format!("Expected a value from {} to {}", a, b)
+        }
+        (Bound::Included(a), Bound::Excluded(b)) => {
+            format!("Expected a value from {} to less than {}", a, b)
+        }
+        (Bound::Included(a), Bound::Unbounded) => {
+            format!("Expected a value of at least {}", a)
+        }
+        (Bound::Unbounded, Bound::Included(b)) => {
+            format!("Expected a value of at most {}", b)
+        }
+        (Bound::Unbounded, Bound::Excluded(b)) => {
+            format!("Expected a value less than {}", b)
+        }
+        _ => "Expected a value in range".to_string(),
+    })
 }
 
 /// Holds data necessary to map a parsed argument to an option
@@ -143,6 +509,141 @@ pub struct Opt {
     /// An index into the shared list of names
     pub name: u16,
     pub kind: OptArgKind,
+    /// Checks each option-argument while parsing
+    pub validator: Option<Validator>,
+    /// The option-argument used when the option isn't given
+    pub default: Option<&'static str>,
+    /// An environment variable to get the option-argument from
+    /// when the option isn't given
+    pub env: Option<&'static str>,
+    /// What happens when the option is given more than once
+    pub repeat: Repeat,
+    /// Splits each option-argument into several values
+    pub delimiter: Option<char>,
+    /// The option-argument of an `OptArgKind::Optional` option
+    /// given without one
+    pub implicit: Option<&'static str>,
+    /// What the help calls the option-argument
+    pub value_name: Option<&'static str>,
+    /// Checks each option-argument of an `OptArgKind::Tuple`
+    /// occurrence by its place, instead of `validator`
+    pub element_validators: &'static [Validator],
+    /// The only option-arguments accepted, when there are any
+    pub possible_values: &'static [&'static str],
+    /// A character class each option-argument's characters must
+    /// be in, e.g. `a-z0-9_-`
+    pub chars: Option<&'static str>,
+}
+impl Opt {
+    pub const fn new(name: u16, kind: OptArgKind) -> Self {
+        if matches!(kind, OptArgKind::Tuple(0)) {
+            panic!("A tuple option expects at least 1 option-argument")
+        }
+        Self {
+            name,
+            kind,
+            validator: None,
+            default: None,
+            env: None,
+            repeat: Repeat::Last,
+            delimiter: None,
+            implicit: None,
+            value_name: None,
+            element_validators: &[],
+            possible_values: &[],
+            chars: None,
+        }
+    }
+    /// Use `value` as the option-argument when the option isn't
+    /// given, and the `env` variable isn't set
+    pub const fn default(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+    /// Use the environment variable `name` as the option-argument
+    /// when the option isn't given
+    pub const fn env(mut self, name: &'static str) -> Self {
+        self.env = Some(name);
+        self
+    }
+    /// Reject option-arguments while parsing when `f` fails
+    pub const fn validator(mut self, f: Validator) -> Self {
+        self.validator = Some(f);
+        self
+    }
+    /// Choose what happens when the option is given more than
+    /// once. The last occurrence wins by default
+    pub const fn repeat(mut self, policy: Repeat) -> Self {
+        self.repeat = policy;
+        self
+    }
+    /// Use `value` as the option-argument of an
+    /// `OptArgKind::Optional` option given without one
+    pub const fn implicit(mut self, value: &'static str) -> Self {
+        self.implicit = Some(value);
+        self
+    }
+    /// Name the option-argument in the help, e.g. `WHEN` for
+    /// `--color[=WHEN]`. It's `VALUE` by default
+    pub const fn value_name(mut self, name: &'static str) -> Self {
+        self.value_name = Some(name);
+        self
+    }
+    /// Only accept these option-arguments. They're listed in the
+    /// help, and by `Router::possible_values()` for completion
+    pub const fn possible_values(
+        mut self,
+        values: &'static [&'static str],
+    ) -> Self {
+        self.possible_values = values;
+        self
+    }
+    /// Only accept non-empty option-arguments made of the
+    /// characters in `class`, which has characters and ranges
+    /// like `a-z`. A '-' at the start or end is a literal '-'
+    pub const fn chars(mut self, class: &'static str) -> Self {
+        self.chars = Some(class);
+        self
+    }
+    /// Check the option-arguments of each `OptArgKind::Tuple`
+    /// occurrence with the validator at the same place
+    pub const fn element_validators(
+        mut self,
+        validators: &'static [Validator],
+    ) -> Self {
+        self.element_validators = validators;
+        self
+    }
+    /// Split each option-argument of a `Multiple` or `KeyValue`
+    /// option on `delimiter`, e.g. `--tags a,b`. Each element is
+    /// checked and added as its own value, and empty ones are
+    /// dropped. A backslash before the delimiter or another
+    /// backslash makes it literal, e.g. `a\,b`
+    pub const fn delimiter(mut self, delimiter: char) -> Self {
+        if !delimiter.is_ascii() || delimiter == '\\' {
+            panic!(
+                "A delimiter must be an ASCII character other than '\\'"
+            )
+        }
+        self.delimiter = Some(delimiter);
+        self
+    }
+}
+
+/// What happens when an option is given more than once. An
+/// occurrence that's ignored isn't counted, and its option-arg
+/// is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Each occurrence counts, and for `OptArgKind::Single`,
+    /// overrides the option-argument before it
+    Last,
+    /// Occurrences after the first are ignored
+    First,
+    /// Giving the option again is an error
+    Error,
+    /// Occurrences after this many are ignored
+    Max(u16),
 }
 
 /// Used during parsing to determine if it needs to be cached
@@ -157,6 +658,56 @@ pub enum OptArgKind {
     /// Expects an option-argument, and an occurrence
     /// of the option adds to a list
     Multiple,
+    /// Like `Multiple`, where each option-argument is a
+    /// `KEY=VALUE` pair, e.g. `--define NAME=VALUE`. The key
+    /// must be non-empty UTF-8, and a validator only checks the
+    /// value
+    KeyValue,
+    /// Only takes an option-argument attached to it, e.g.
+    /// `--color=never` or `-cnever`, so the next argument is never
+    /// taken. Given without one, its value is the `Opt::implicit()`
+    /// value. Without an implicit value, it has no value, even when
+    /// an earlier occurrence had one
+    Optional,
+    /// Expects exactly this many option-arguments each time
+    /// it's given, e.g. `--rename OLD NEW`. Like `Multiple`,
+    /// occurrences add to a list, and `Arg::tuples()` splits it
+    /// back into occurrences. It can't have a default or
+    /// environment variable
+    Tuple(u8),
+}
+impl OptArgKind {
+    /// Whether occurrences add to a list, instead of overriding
+    /// the option-argument before
+    const fn is_list(&self) -> bool {
+        matches!(self, Self::Multiple | Self::KeyValue | Self::Tuple(_))
+    }
+}
+
+/// What the parser does with an argument that doesn't match
+/// an option or segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownArgs {
+    /// Skip over the argument
+    Ignore,
+    /// Stop parsing and return a `ParseError`
+    Error,
+    /// Keep the argument in `Context::unknown_args()`
+    Collect,
+}
+
+/// Whether the parser records the segment each option is
+/// given at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionPlacement {
+    /// Collect options together wherever they're given
+    Anywhere,
+    /// Also keep the values given at each segment, for
+    /// `Context::opt_at()`
+    Record,
+    /// Like `Record`, but an option given before the segment
+    /// that declares it in an `OptGroup` is an error
+    Strict,
 }
 
 #[derive(Clone, Copy)]
@@ -164,6 +715,7 @@ pub enum OptGroupRules {
     AnyOf,
     OneOf,
     Required,
+    Cascades = 4,
 }
 
 /// The driver of the parser that produces a `Context` from
@@ -176,6 +728,11 @@ pub struct Router {
     tree: &'static [TreeNode],
     segments: &'static [Segment],
     actions: &'static [Action],
+    // Path parameters only match arguments that pass their
+    // validator
+    validators: &'static [Option<Validator>],
+    // Each segment's named operands
+    named_operands: &'static [&'static [Operand]],
     // Bitmask: exclusive, required, and cascades bools
     // The u8s act as `OptGroupRules`, but are stored
     // as u8s to avoid casting at runtime
@@ -190,6 +747,14 @@ pub struct Router {
     // segments so for each type, less needs to be searched
     names: &'static [&'static str],
     help_opt_index: Option<u16>,
+    unknown_args: UnknownArgs,
+    option_placement: OptionPlacement,
+    record_events: bool,
+    /// Accept unambiguous prefixes of option and segment names
+    abbreviations: bool,
+    /// How deep response files can include others, or 0 when
+    /// they aren't expanded
+    response_file_depth: u8,
 }
 impl Router {
     /// Manually create a `Router` from parts obtained by
@@ -201,6 +766,8 @@ impl Router {
         tree: &'static [TreeNode],
         segments: &'static [Segment],
         actions: &'static [Action],
+        validators: &'static [Option<Validator>],
+        named_operands: &'static [&'static [Operand]],
         // [router::DocGen; _CMD_COUNT.0],
         opt_group_rules: &'static [u8],
         opt_groups: &'static [&[u16]],
@@ -216,6 +783,8 @@ impl Router {
             tree,
             segments,
             actions,
+            validators,
+            named_operands,
             // docs: &seg_parts.3,
             opt_group_rules,
             opt_groups,
@@ -224,8 +793,143 @@ impl Router {
             names,
             // summaries: &seg_parts.7,
             help_opt_index,
+            unknown_args: UnknownArgs::Ignore,
+            option_placement: OptionPlacement::Anywhere,
+            record_events: false,
+            response_file_depth: 0,
+            abbreviations: false,
         }
     }
+    /// Choose what happens to unrecognized options, short
+    /// options, and arguments that don't match a segment once
+    /// the selected segment's operands are filled. They're
+    /// ignored by default.
+    ///
+    /// ```ignore
+    /// const R: Router = router!(O, C).unknown_args(UnknownArgs::Error);
+    /// ```
+    pub const fn unknown_args(mut self, policy: UnknownArgs) -> Self {
+        self.unknown_args = policy;
+        self
+    }
+    /// Choose whether the segment each option is given at is
+    /// recorded, for `Context::opt_at()`. Options are collected
+    /// together wherever they're given by default.
+    ///
+    /// ```ignore
+    /// const R: Router =
+    ///     router!(O, C).option_placement(OptionPlacement::Strict);
+    /// ```
+    pub const fn option_placement(
+        mut self,
+        placement: OptionPlacement,
+    ) -> Self {
+        self.option_placement = placement;
+        self
+    }
+    /// Record the order of the parsed arguments, for
+    /// `Context::events()`
+    ///
+    /// ```ignore
+    /// const R: Router = router!(O, C).record_events();
+    /// ```
+    pub const fn record_events(mut self) -> Self {
+        self.record_events = true;
+        self
+    }
+    /// Replace each `@path` argument with the arguments in the
+    /// file at `path` before parsing, which are separated by
+    /// whitespace and can be quoted like in a shell. A response
+    /// file can include others up to `max_depth` files deep. `@@`
+    /// is a literal '@', and arguments after `--` aren't expanded.
+    ///
+    /// ```ignore
+    /// const R: Router = router!(O, C).response_files(8);
+    /// ```
+    pub const fn response_files(mut self, max_depth: u8) -> Self {
+        self.response_file_depth = max_depth;
+        self
+    }
+    /// Accept a prefix of a long option's name, or of a segment's
+    /// name, when only one name starts with it, like `--verb` for
+    /// `--verbose`. Exact names are matched first, and an
+    /// abbreviated segment name is matched before a path
+    /// parameter. A prefix of more than one name is an error.
+    ///
+    /// ```ignore
+    /// const R: Router = router!(O, C).abbreviations();
+    /// ```
+    pub const fn abbreviations(mut self) -> Self {
+        self.abbreviations = true;
+        self
+    }
+    /// The option-arguments an option accepts, from
+    /// `Opt::possible_values()`, e.g. for shell completion. It's
+    /// empty when any are accepted
+    pub fn possible_values(
+        &self,
+        option: impl Into<usize>,
+    ) -> &'static [&'static str] {
+        self.options[option.into()].possible_values
+    }
+    /// The indexes of the option groups that apply to a segment:
+    /// its own, then the cascading groups of its ancestors up to
+    /// an isolated segment
+    fn segment_opt_groups(
+        &self,
+        segment: u16,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let own = move |segment: u16| {
+            let groups = self.segments[segment as usize].opt_groups >> 12;
+            let index = (self.segments[segment as usize].opt_groups << 4
+                >> 4) as usize;
+            index..index + groups as usize
+        };
+        let ancestors = core::iter::successors(Some(segment), move |&s| {
+            (s != 0 && !self.segments[s as usize].isolated)
+                .then(|| self.tree[s as usize].parent)
+        });
+        own(segment).chain(ancestors.skip(1).flat_map(move |ancestor| {
+            own(ancestor).filter(move |&g| {
+                self.opt_group_rules[g] & OptGroupRules::Cascades as u8
+                    != 0
+            })
+        }))
+    }
+    /// The first child that's selected when a route ends without
+    /// reaching it: an optional path parameter or a catch-all
+    fn implicit_child(&self, segment: u16) -> Option<u16> {
+        self.children(segment).find(|&child| {
+            let name =
+                self.names[self.segments[child as usize].name as usize];
+            name.starts_with('*')
+                || (name.starts_with(':') && name.ends_with('?'))
+        })
+    }
+    /// The child that takes an argument no other child matches: a
+    /// catch-all, e.g. `*rest`. Once it's selected, it swallows
+    /// every remaining argument as it is, including options, `--`,
+    /// and `--help`, which are then only in `Context::rest()`
+    fn catch_all_child(&self, segment: u16) -> Option<u16> {
+        self.children(segment).find(|&child| {
+            self.names[self.segments[child as usize].name as usize]
+                .starts_with('*')
+        })
+    }
+    /// The indexes of a segment's direct children
+    fn children(&self, segment: u16) -> impl Iterator<Item = u16> + '_ {
+        let end = segment + self.tree[segment as usize].child_span + 1;
+        let mut child = segment + 1;
+        std::iter::from_fn(move || {
+            if child >= end {
+                return None;
+            }
+            let current = child;
+            // Skip to next sibling segment
+            child += self.tree[child as usize].child_span + 1;
+            Some(current)
+        })
+    }
     /// Parse arbitrary sets of OsStrings, which could be useful
     /// if you need to programmatically call a command with
     /// arguments. It's mainly used for testing the parser
@@ -234,13 +938,13 @@ impl Router {
     pub fn parse(
         &self,
         args: impl IntoIterator<Item = OsString>,
-    ) -> io::Result<Context> {
+    ) -> Result<Context, ParseError> {
         parse_cli_route(self, args)
     }
     /// Run the parser using the arguments passed to the process,
     /// without running the action if a command was found.
     #[inline(always)]
-    pub fn context(&self) -> io::Result<Context> {
+    pub fn context(&self) -> Result<Context, ParseError> {
         parse_cli_route(self, std::env::args_os().skip(1))
     }
     /// Run the parser using the arguments passed to the process,
@@ -255,7 +959,9 @@ impl Router {
 /// separately)
 #[derive(Debug, Clone, Copy)]
 pub struct Segment {
+    /// The most operands, with `min_operands` the range it takes
     operands: u16,
+    min_operands: u16,
     /// First 4 bits specify a length of groups as an offset,
     /// from the index. The remaining 12 bits are for the
     /// index.
@@ -267,6 +973,8 @@ pub struct Segment {
     opt_groups: u16,
     /// An index into the shared list of names
     name: u16,
+    /// Doesn't inherit cascading option groups
+    isolated: bool,
 }
 
 /// Holds structural information for the segment
@@ -281,18 +989,72 @@ fn add_found_option(
     options: &[Opt],
     c: &mut Context,
     next_arg: Option<OsString>,
-) -> io::Result<()> {
+    position: u16,
+) -> Result<(), ParseError> {
     next_arg
-        // Missing option-argument
-        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))
+        .ok_or_else(|| ParseError::MissingArgument {
+            option: index as u16,
+            name: c.router.names[options[index].name as usize],
+            position,
+        })
         .and_then(|val| {
+            let checked = match options[index].kind {
+                OptArgKind::KeyValue => match split_pair(&val) {
+                    Some((_, value)) => value,
+                    None => {
+                        return Err(ParseError::Conversion {
+                            option: index as u16,
+                            name: c.router.names
+                                [options[index].name as usize],
+                            arg: val,
+                            reason: "Expected KEY=VALUE".to_string(),
+                        })
+                    }
+                },
+                _ => &val,
+            };
+            let validator = match options[index].kind {
+                // The place of the value in its occurrence
+                OptArgKind::Tuple(n) => {
+                    let stored = c
+                        .option_args
+                        .iter()
+                        .find(|(o, _)| *o == index as u16)
+                        .map_or(0, |&(_, r)| {
+                            c.arg_ranges[r as usize].len()
+                        });
+                    options[index]
+                        .element_validators
+                        .get(stored % n as usize)
+                        .copied()
+                }
+                _ => options[index].validator,
+            };
+            if let Err(reason) = constrain(&options[index], checked) {
+                return Err(ParseError::Conversion {
+                    option: index as u16,
+                    name: c.router.names[options[index].name as usize],
+                    arg: val,
+                    reason,
+                });
+            }
+            if let Some(validate) = validator {
+                if let Err(reason) = validate(checked) {
+                    return Err(ParseError::Conversion {
+                        option: index as u16,
+                        name: c.router.names[options[index].name as usize],
+                        arg: val,
+                        reason,
+                    });
+                }
+            }
             match (
                 c.option_args
                     .iter()
                     .position(|(saved_opt, _)| *saved_opt == index as u16),
                 &options[index].kind,
             ) {
-                (Some(found), OptArgKind::Multiple) => {
+                (Some(found), kind) if kind.is_list() => {
                     // Option was given before
 
                     if c.arg_ranges[c.option_args[found].1 as usize].end
@@ -304,32 +1066,31 @@ fn add_found_option(
                         return Ok(());
                     }
                     // Adjust options found after this
-                    let mut i = c.arg_ranges
-                        [c.option_args[found].1 as usize]
-                        .end as usize;
+                    let i =
+                        c.arg_ranges[c.option_args[found].1 as usize].end;
 
-                    c.saved_args.insert(i, val);
+                    c.saved_args.insert(i as usize, val);
                     c.arg_ranges[c.option_args[found].1 as usize].end += 1;
 
-                    while i < c.option_args.len() {
-                        match options[c.option_args[i].0 as usize].kind {
-                            OptArgKind::Single => {
-                                c.option_args[i].1 += 1;
-                            }
-                            OptArgKind::Multiple => {
-                                c.arg_ranges
-                                    [c.option_args[i].1 as usize]
-                                    .start += 1;
-                                c.arg_ranges
-                                    [c.option_args[i].1 as usize]
-                                    .end += 1;
+                    // Shift every other option's values after it. A
+                    // single value's index is into `saved_args`, while
+                    // a list's is into `arg_ranges`
+                    for j in 0..c.option_args.len() {
+                        let (o, start) = c.option_args[j];
+                        if j == found {
+                            continue;
+                        }
+                        if !options[o as usize].kind.is_list() {
+                            if start >= i {
+                                c.option_args[j].1 += 1;
                             }
-                            _ => (),
+                        } else if c.arg_ranges[start as usize].start >= i {
+                            c.arg_ranges[start as usize].start += 1;
+                            c.arg_ranges[start as usize].end += 1;
                         }
-                        i += 1;
                     }
                 }
-                (None, OptArgKind::Multiple) => {
+                (None, kind) if kind.is_list() => {
                     // Option not given before
                     c.option_args
                         .push((index as u16, c.arg_ranges.len() as u16));
@@ -352,6 +1113,503 @@ fn add_found_option(
         })
 }
 
+/// Check an option-argument against an option's possible values
+/// and character class
+fn constrain(opt: &Opt, arg: &OsStr) -> Result<(), String> {
+    if !opt.possible_values.is_empty()
+        && !opt.possible_values.iter().any(|v| OsStr::new(v) == arg)
+    {
+        return Err(format!(
+            "Expected one of: {}",
+            opt.possible_values.join(", ")
+        ));
+    }
+    if let Some(class) = opt.chars {
+        let valid = arg.to_str().is_some_and(|a| {
+            !a.is_empty() && a.chars().all(|ch| in_char_class(class, ch))
+        });
+        if !valid {
+            return Err(format!("Expected only the characters {}", class));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `ch` is in a character class like `a-z0-9_-`
+fn in_char_class(class: &str, ch: char) -> bool {
+    let class = class.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < class.len() {
+        // A '-' between two characters makes a range
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if (class[i]..=class[i + 2]).contains(&ch) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if class[i] == ch {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+/// Add an option-argument, or each element of it when the option
+/// has a delimiter. Returns how many values were added
+fn add_option_arg(
+    option: usize,
+    c: &mut Context,
+    arg: OsString,
+    position: u16,
+) -> Result<usize, ParseError> {
+    let options = c.router.options;
+    match (options[option].delimiter, &options[option].kind) {
+        (Some(delimiter), OptArgKind::Multiple | OptArgKind::KeyValue) => {
+            let elements = split_delimited(&arg, delimiter as u8);
+            let added = elements.len();
+            for element in elements {
+                add_found_option(
+                    option,
+                    options,
+                    c,
+                    Some(element),
+                    position,
+                )?;
+            }
+            Ok(added)
+        }
+        _ => add_found_option(option, options, c, Some(arg), position)
+            .map(|_| 1),
+    }
+}
+
+/// The non-empty elements of `arg` between each `delimiter`. A
+/// backslash before the delimiter or another backslash is
+/// removed, keeping what follows as it is
+fn split_delimited(arg: &OsStr, delimiter: u8) -> Vec<OsString> {
+    let bytes = arg.as_encoded_bytes();
+    // SAFETY: Only called with bounds next to ASCII characters
+    let part = |range: Range<usize>| unsafe {
+        OsStr::from_encoded_bytes_unchecked(&bytes[range])
+    };
+    let mut elements = Vec::new();
+    let mut element = OsString::new();
+    // The start of the part of `element` not added yet
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\'
+                if i + 1 < bytes.len()
+                    && (bytes[i + 1] == delimiter
+                        || bytes[i + 1] == b'\\') =>
+            {
+                element.push(part(start..i));
+                start = i + 1;
+                i += 2;
+                continue;
+            }
+            b if b == delimiter => {
+                element.push(part(start..i));
+                if !element.is_empty() {
+                    elements.push(std::mem::take(&mut element));
+                }
+                start = i + 1;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    element.push(part(start..bytes.len()));
+    if !element.is_empty() {
+        elements.push(element);
+    }
+    elements
+}
+
+/// Split a `KEY=VALUE` option-argument at its first '='. The key
+/// must be non-empty UTF-8
+fn split_pair(arg: &OsStr) -> Option<(&str, &OsStr)> {
+    let bytes = arg.as_encoded_bytes();
+    let eq = bytes.iter().position(|&b| b == b'=')?;
+    let key = std::str::from_utf8(&bytes[..eq])
+        .ok()
+        .filter(|k| !k.is_empty())?;
+    // SAFETY: The bytes are split right after an ASCII character
+    let value =
+        unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[eq + 1..]) };
+    Some((key, value))
+}
+
+/// Record the segment an option was given at, with the `added`
+/// values that were just added for it, when the `Router` records
+/// them
+fn place_option(
+    c: &mut Context,
+    option: usize,
+    position: u16,
+    added: usize,
+) {
+    events::record_option(c, option, added);
+    if let OptionPlacement::Anywhere = c.router.option_placement {
+        return;
+    }
+    // Without values added, earlier ones aren't credited to it
+    let args = c.opt(option).args;
+    let values = args[args.len() - added.min(args.len())..].to_vec();
+    c.placements.push((
+        option as u16,
+        c.selected,
+        position,
+        values.len() as u16,
+    ));
+    c.placed_args.extend(values);
+}
+
+/// Add each of a group of short options. Like getopt, the first
+/// one that expects an option-arg takes the rest of the group as
+/// its value, or else the next arg
+fn add_short_options<I: Iterator<Item = OsString>>(
+    c: &mut Context,
+    lexer: &mut Lexer<I>,
+    shorts: &ShortOpts,
+    option_positions: &mut [u16],
+    position: u16,
+) -> Result<(), ParseError> {
+    let router = c.router;
+    for (i, ch) in shorts.chars() {
+        let o = match router
+            .short_option_mappers
+            .iter()
+            .find(|(_, mapper)| *mapper == ch)
+        {
+            Some(&(o, _)) => o as usize,
+            None => {
+                let e = ParseError::UnknownOption {
+                    arg: format!("-{}", ch).into(),
+                    position,
+                    segment: c.selected,
+                    suggestions: Vec::new(),
+                };
+                unknown_arg(c, e)?;
+                continue;
+            }
+        };
+        add_option(
+            c,
+            o,
+            shorts.attached(i),
+            || lexer.value(),
+            option_positions,
+            position,
+        )?;
+        if let OptArgKind::KeyOnly = router.options[o].kind {
+            continue;
+        }
+        break;
+    }
+    Ok(())
+}
+
+/// Add an occurrence of an option, and its option-arg when it
+/// expects one, by the option's `Repeat` policy. The option-arg
+/// is the `attached` one, or else the one from `next`, unless
+/// it's optional
+fn add_option(
+    c: &mut Context,
+    option: usize,
+    attached: Option<&str>,
+    mut next: impl FnMut() -> Option<OsString>,
+    option_positions: &mut [u16],
+    position: u16,
+) -> Result<(), ParseError> {
+    let router = c.router;
+    let opt = &router.options[option];
+    let count = c.option_occurrences[option];
+    let keep = match opt.repeat {
+        Repeat::Last => true,
+        Repeat::First => count == 0,
+        Repeat::Max(max) => count < max,
+        Repeat::Error if count > 0 => {
+            return Err(ParseError::RepeatedOption {
+                option: option as u16,
+                name: router.names[opt.name as usize],
+                position,
+            })
+        }
+        Repeat::Error => true,
+    };
+    let missing = |found: usize| match opt.kind {
+        OptArgKind::Tuple(expected) => ParseError::MissingArguments {
+            option: option as u16,
+            name: router.names[opt.name as usize],
+            position,
+            expected,
+            found: found as u8,
+        },
+        _ => ParseError::MissingArgument {
+            option: option as u16,
+            name: router.names[opt.name as usize],
+            position,
+        },
+    };
+    // An occurrence that isn't kept still takes its option-args
+    let value = match opt.kind {
+        OptArgKind::KeyOnly => None,
+        OptArgKind::Optional => {
+            attached.or(opt.implicit).map(OsString::from)
+        }
+        _ => Some(
+            attached
+                .map(OsString::from)
+                .or_else(&mut next)
+                .ok_or_else(|| missing(0))?,
+        ),
+    };
+    // The rest of a tuple's option-args
+    let mut rest = Vec::new();
+    if let OptArgKind::Tuple(n) = opt.kind {
+        while rest.len() + 1 < n as usize {
+            rest.push(next().ok_or_else(|| missing(rest.len() + 1))?);
+        }
+    }
+    if !keep {
+        return Ok(());
+    }
+    c.option_occurrences[option] = count.saturating_add(1);
+    option_positions[option] = position;
+    if let (OptArgKind::Optional, None) = (&opt.kind, &value) {
+        // A bare occurrence replaces an earlier option-arg with none
+        c.option_args.retain(|&(o, _)| o as usize != option);
+    }
+    let mut added = 0;
+    if let Some(value) = value {
+        added += add_option_arg(option, c, value, position)?;
+    }
+    for value in rest {
+        added += add_option_arg(option, c, value, position)?;
+    }
+    place_option(c, option, position, added);
+    Ok(())
+}
+
+/// Sort the options recorded by `place_option()` for lookups,
+/// and with `OptionPlacement::Strict`, check that each option
+/// came after the segment that declares it
+fn place_options(c: &mut Context) -> Result<(), ParseError> {
+    let router = c.router;
+    let mut order = (0..c.placements.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (c.placements[i].0, c.placements[i].1));
+    // Where each placement's option-args start
+    let mut starts = Vec::with_capacity(c.placements.len());
+    let mut start = 0;
+    for p in c.placements.iter() {
+        starts.push(start);
+        start += p.3 as usize;
+    }
+    let mut placed_args = std::mem::take(&mut c.placed_args);
+    c.placed_args = order
+        .iter()
+        .flat_map(|&i| starts[i]..starts[i] + c.placements[i].3 as usize)
+        .map(|a| std::mem::take(&mut placed_args[a]))
+        .collect();
+    c.placements = order.iter().map(|&i| c.placements[i]).collect();
+
+    if router.option_placement != OptionPlacement::Strict {
+        return Ok(());
+    }
+    for &(option, segment, position, _) in c.placements.iter() {
+        let declares = |s: u16| {
+            router
+                .segment_opt_groups(s)
+                .any(|g| router.opt_groups[g].contains(&option))
+        };
+        if declares(segment) {
+            continue;
+        }
+        // Segments on the route after the one it was given at
+        let mut later = c.selected;
+        while later != segment && later != 0 {
+            if declares(later) {
+                return Err(ParseError::MisplacedOption {
+                    option,
+                    name: router.names
+                        [router.options[option as usize].name as usize],
+                    position,
+                    segment: later,
+                    segment_name: router.names
+                        [router.segments[later as usize].name as usize],
+                });
+            }
+            later = router.tree[later as usize].parent;
+        }
+    }
+    Ok(())
+}
+
+/// The option with a name, or with `Router::abbreviations()`, the
+/// only option the name is a prefix of. Otherwise, the names of
+/// the options it's a prefix of
+fn find_option(
+    router: &Router,
+    name: &str,
+) -> Result<usize, Vec<&'static str>> {
+    let name_of = |o: &Opt| router.names[o.name as usize];
+    let start =
+        match router.options.binary_search_by(|o| name_of(o).cmp(name)) {
+            Ok(op) => return Ok(op),
+            Err(start) => start,
+        };
+    if !router.abbreviations || name.is_empty() {
+        return Err(Vec::new());
+    }
+    // Names with the prefix are sorted right after it
+    let candidates = router.options[start..]
+        .iter()
+        .map(name_of)
+        .take_while(|n| n.starts_with(name))
+        .collect::<Vec<_>>();
+    match candidates.len() {
+        1 => Ok(start),
+        _ => Err(candidates),
+    }
+}
+
+/// Add an operand of the selected segment, checked by its
+/// `Operand`'s validator when it has one
+fn add_operand(c: &mut Context, arg: OsString) -> Result<(), ParseError> {
+    let named = c.router.named_operands[c.selected as usize];
+    let index = c.operands.len() - c.path_params as usize;
+    let operand =
+        named.get(index).or_else(|| named.last().filter(|o| o.many));
+    if let Some(&Operand {
+        name,
+        validator: Some(f),
+        ..
+    }) = operand
+    {
+        if let Err(reason) = f(&arg) {
+            return Err(ParseError::InvalidOperand {
+                segment: c.selected,
+                name,
+                arg,
+                reason,
+            });
+        }
+    }
+    c.operands.push(arg);
+    Ok(())
+}
+
+/// Apply the `Router`'s `UnknownArgs` policy to an argument
+/// that wasn't recognized
+fn unknown_arg(c: &mut Context, e: ParseError) -> Result<(), ParseError> {
+    // Operands beyond a segment's range are always an error,
+    // unless it takes none
+    if let ParseError::TooManyOperands { segment, .. } = e {
+        if c.router.segments[segment as usize].operands > 0 {
+            return Err(e);
+        }
+    }
+    match c.router.unknown_args {
+        UnknownArgs::Ignore => Ok(()),
+        UnknownArgs::Error => Err(add_suggestions(c, e)),
+        UnknownArgs::Collect => {
+            match e {
+                ParseError::UnknownOption { arg, .. }
+                | ParseError::UnknownSegment { arg, .. }
+                | ParseError::TooManyOperands { arg, .. }
+                | ParseError::InvalidUtf8 { arg, .. } => {
+                    c.unknown_args.push(arg)
+                }
+                _ => (),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Fill in the names an unrecognized option or segment may
+/// have meant, out of what the selected segment accepts
+fn add_suggestions(c: &Context, mut e: ParseError) -> ParseError {
+    let router = c.router;
+    match &mut e {
+        ParseError::UnknownOption {
+            arg, suggestions, ..
+        } => {
+            if let Some(name) =
+                arg.to_str().and_then(|a| a.strip_prefix(OPTION_PREFIX))
+            {
+                *suggestions = closest_names(
+                    name,
+                    (0..router.options.len() as u16)
+                        .filter(|o| {
+                            let mut groups = router
+                                .segment_opt_groups(c.selected)
+                                .peekable();
+                            groups.peek().is_none()
+                                || groups.any(|g| {
+                                    router.opt_groups[g].contains(o)
+                                })
+                        })
+                        .map(|o| {
+                            router.names
+                                [router.options[o as usize].name as usize]
+                        }),
+                );
+            }
+        }
+        ParseError::UnknownSegment {
+            arg,
+            suggestions,
+            segment,
+            ..
+        } => {
+            if let Some(name) = arg.to_str() {
+                *suggestions = closest_names(
+                    name,
+                    router
+                        .children(*segment)
+                        .map(|s| {
+                            router.names
+                                [router.segments[s as usize].name as usize]
+                        })
+                        .filter(|n| !n.starts_with([':', '*'])),
+                );
+            }
+        }
+        _ => (),
+    }
+    e
+}
+
+/// The error for an argument that doesn't match a child of the
+/// selected segment, or is an extra operand if it has none
+fn unmatched_segment(
+    c: &Context,
+    arg: OsString,
+    position: u16,
+) -> ParseError {
+    if c.router.tree[c.selected as usize].child_span > 0 {
+        ParseError::UnknownSegment {
+            arg,
+            position,
+            segment: c.selected,
+            suggestions: Vec::new(),
+        }
+    } else {
+        ParseError::TooManyOperands {
+            arg,
+            position,
+            segment: c.selected,
+            segment_name: c.router.names
+                [c.router.segments[c.selected as usize].name as usize],
+        }
+    }
+}
+
 /// Report the estimated size of a `Context` and its
 /// `Router` in bytes
 #[cfg(debug_assertions)]
@@ -360,7 +1618,7 @@ pub fn context_size(c: &Context) {
     assert_eq!(size_of::<(u16, u16)>(), 4);
     assert_eq!(size_of::<Range<u16>>(), 4);
     assert_eq!(size_of::<TreeNode>(), 4);
-    assert_eq!(size_of::<Segment>(), 6);
+    assert_eq!(size_of::<Segment>(), 10);
     assert_eq!(size_of::<(u16, char)>(), 8);
 
     let counts = &[
@@ -378,6 +1636,16 @@ pub fn context_size(c: &Context) {
         size_of_val(&c.selected),
         size_of_val(&c.operands_end),
         size_of_val(&c.path_params),
+        size_of_val(&c.unknown_args),
+        c.unknown_args.iter().map(|x| size_of_val(x)).sum::<usize>(),
+        size_of_val(&c.placements),
+        c.placements.iter().map(|x| size_of_val(x)).sum::<usize>(),
+        size_of_val(&c.placed_args),
+        c.placed_args.iter().map(|x| size_of_val(x)).sum::<usize>(),
+        size_of_val(&c.events),
+        c.events.iter().map(|x| size_of_val(x)).sum::<usize>(),
+        size_of_val(&c.event_args),
+        c.event_args.iter().map(|x| size_of_val(x)).sum::<usize>(),
         // Router sizes
         size_of_val(&c.router),
         size_of_val(&c.router.tree),
@@ -448,6 +1716,16 @@ option_occurrences: {}
 selected: {}
 operands_end: {}
 path_params: {}
+unknown_args: {}
+  sum: {}
+placements: {}
+  sum: {}
+placed_args: {}
+  sum: {}
+events: {}
+  sum: {}
+event_args: {}
+  sum: {}
 --------------------------
 Router size: {}
 tree: {}
@@ -501,6 +1779,16 @@ Total: {}",
         counts[29],
         counts[30],
         counts[31],
+        counts[32],
+        counts[33],
+        counts[34],
+        counts[35],
+        counts[36],
+        counts[37],
+        counts[38],
+        counts[39],
+        counts[40],
+        counts[41],
         counts.iter().sum::<usize>()
     );
 }
@@ -508,16 +1796,28 @@ Total: {}",
 /// Find the chunk of code to run, it's options, and
 /// it's operands
 ///
-/// Currently, unrecognized options are ignored
+/// Unrecognized arguments are handled according to
+/// `Router::unknown_args()`
 pub fn parse_cli_route(
     router: &Router,
     args: impl IntoIterator<Item = OsString>,
-) -> io::Result<Context> {
-    let mut args = args.into_iter();
-    let mut c = Context {
+) -> Result<Context, ParseError> {
+    if router.response_file_depth > 0 {
+        let args =
+            response_file::expand(args, router.response_file_depth)?;
+        return parse_tokens(router, Lexer::new(args));
+    }
+    parse_tokens(router, Lexer::new(args))
+}
+
+fn parse_tokens(
+    router: &Router,
+    mut lexer: Lexer<impl Iterator<Item = OsString>>,
+) -> Result<Context<'_>, ParseError> {
+    let mut c = Context {
         operands: Vec::new(),
-        saved_args: Vec::with_capacity(args.size_hint().0),
-        option_args: Vec::<(u16, u16)>::with_capacity(args.size_hint().0),
+        saved_args: Vec::with_capacity(lexer.size_hint().0),
+        option_args: Vec::<(u16, u16)>::with_capacity(lexer.size_hint().0),
         arg_ranges: Vec::<Range<u16>>::new(),
         option_occurrences: vec![0; router.options.len()],
         router,
@@ -527,278 +1827,300 @@ pub fn parse_cli_route(
         // begin, indicating how many path params were
         // found
         path_params: 0,
+        unknown_args: Vec::new(),
+        placements: Vec::new(),
+        placed_args: Vec::new(),
+        events: Vec::new(),
+        event_args: Vec::new(),
     };
-    // Used to validate option groups since key-only options
-    // don't add to `option_args`
-    let mut options_found = 0u16;
+    // Where each option was last found, used to report an
+    // option the selected segment's groups don't allow
+    let mut option_positions = vec![0u16; router.options.len()];
     // Since the first arg, the name of the program,
     // is always skipped we don't need to match on it
     let mut tree_index = 1;
 
-    while let Some(arg) = args.next() {
-        let checked_arg = match arg.to_str() {
-            Some(a) => a,
-            _ => {
-                // Won't match any segment or option,
-                // since they're all UTF-8, so it can
-                // only be an operand
-                if router.segments[c.selected as usize].operands
-                    != (c.operands.len() - c.path_params as usize) as u16
-                {
-                    c.operands.push(arg);
-                } else
-                // Either an option with invalid UTF-8 or an unrecognized
-                // segment. Valid options will later obtain option-args
-                // without checking UTF-8
-
-                // Will always have bytes because an empty string
-                // would've passed UTF-8 checks
-                if arg.as_encoded_bytes()[0] == b'-' {
-                    // Invalid option
+    while let Some(token) = lexer.next() {
+        let position = lexer.position() as u16;
+        // Everything after a segment with a catch-all child is
+        // captured, unless it's a name that matches another child
+        let catch_all = router.catch_all_child(c.selected);
+        if let Some(rest) = catch_all {
+            if !matches!(&token, Token::Value(a) if a.to_str().is_some()) {
+                c.selected = rest;
+                events::record(&mut c, events::RawEvent::Segment(rest));
+                c.operands.push(token.into_arg());
+                c.operands.extend(lexer.map(Token::into_arg));
+                c.operands_end = c.operands.len() as u16;
+                break;
+            }
+        }
+        let has_operands_left = router.segments[c.selected as usize]
+            .operands
+            != (c.operands.len() - c.path_params as usize) as u16;
+        let arg = match token {
+            Token::Value(arg) => arg,
+            Token::Raw(arg) => {
+                // Won't match any option, since they're all UTF-8,
+                // so it can only be an operand. Valid options will
+                // later obtain option-args without checking UTF-8
+                if has_operands_left {
+                    add_operand(&mut c, arg)?;
                 } else {
-                    // Invalid segment
+                    unknown_arg(
+                        &mut c,
+                        ParseError::InvalidUtf8 { arg, position },
+                    )?;
                 }
                 continue;
             }
-        };
-
-        if checked_arg.starts_with('-') {
-            let mut chars = checked_arg.chars().skip(1).peekable();
-
-            // Options
-            // Unwrap safe because we already checked this index above
-            match chars.peek() {
-                None => {
-                    // Special '-' stdin
-                    c.operands.push(arg);
+            // It's never a segment name or path parameter, so it's
+            // an operand, even when the segment has no room for it
+            Token::Stdin => {
+                if !has_operands_left {
+                    let segment = router.segments[c.selected as usize];
+                    return Err(ParseError::TooManyOperands {
+                        arg: "-".into(),
+                        position,
+                        segment: c.selected,
+                        segment_name: router.names[segment.name as usize],
+                    });
                 }
-                Some('-') => {
-                    if checked_arg.len() == 2 {
-                        c.operands_end = c.operands.len() as u16;
-                        c.operands.extend(args);
-                        break;
-                    }
-                    // Long
-
-                    #[cfg(feature = "eq-separator")]
-                    let (name, eq_index) = match checked_arg.find('=') {
-                        // Decision:
-                        // We duplicate the range `start` logic since
-                        // the resulting `RangeFrom` expression has less
-                        // instructions than a `Range` expression
-                        Some(eq) => {
-                            if eq == 2 || eq == checked_arg.len() - 1 {
-                                // ! Error: invalid eq sign
+                add_operand(&mut c, "-".into())?;
+                continue;
+            }
+            Token::Terminator => {
+                events::record(&mut c, events::RawEvent::Terminator);
+                c.operands_end = c.operands.len() as u16;
+                c.operands.extend(lexer.map(Token::into_arg));
+                break;
+            }
+            Token::Long(long) => {
+                let mut name = long.name();
+                let mut found = find_option(router, name);
+                let mut attached = long.value();
+                // An optional option-arg can always be attached
+                // with '=', even without the eq-separator feature
+                if let (Err(_), Some((before, value))) =
+                    (&found, long.name().split_once('='))
+                {
+                    match find_option(router, before) {
+                        Ok(op) => {
+                            if let OptArgKind::Optional =
+                                router.options[op].kind
+                            {
+                                found = Ok(op);
+                                attached = Some(value);
                             }
-                            (
-                                &checked_arg[{
-                                    #[cfg(
-                                        feature = "single-hyphen-option-names"
-                                    )]
-                                    {
-                                        1
-                                    }
-                                    #[cfg(not(
-                                        feature = "single-hyphen-option-names"
-                                    ))]
-                                    {
-                                        2
-                                    }
-                                }
-                                    ..eq],
-                                Some(eq),
-                            )
                         }
-                        _ => (
-                            &checked_arg[{
-                                #[cfg(
-                                    feature = "single-hyphen-option-names"
-                                )]
-                                {
-                                    1
-                                }
-                                #[cfg(not(
-                                    feature = "single-hyphen-option-names"
-                                ))]
-                                {
-                                    2
-                                }
-                            }..],
-                            None,
-                        ),
-                    };
-                    #[cfg(not(feature = "eq-separator"))]
-                    let name = &checked_arg[{
-                        #[cfg(feature = "single-hyphen-option-names")]
-                        {
-                            1
-                        }
-                        #[cfg(not(
-                            feature = "single-hyphen-option-names"
-                        ))]
-                        {
-                            2
-                        }
-                    }..];
-
-                    if let Ok(op) =
-                        // router.options.iter().position(|mapper| {
-                        //     router.names[mapper.name as usize].as_bytes()
-                        //         [0]
-                        //         == name.as_bytes()[0]
-                        //         && router.names[mapper.name as usize]
-                        //             == name
-                        // })
-                        router.options.binary_search_by(|o| {
-                                router.names[o.name as usize].cmp(name)
-                            })
-                    {
-                        // Found
-                        if c.option_occurrences[op] == 0 {
-                            options_found += 1;
+                        Err(candidates) if candidates.len() > 1 => {
+                            name = before;
+                            found = Err(candidates);
                         }
-                        c.option_occurrences[op] += 1;
-                        if let OptArgKind::KeyOnly =
-                            router.options[op].kind
-                        {
-                        } else {
-                            add_found_option(
-                                op,
-                                router.options,
-                                &mut c,
-                                #[cfg(feature = "eq-separator")]
-                                eq_index
-                                    .and_then(|pos| {
-                                        Some(checked_arg[pos + 1..].into())
-                                    })
-                                    .or_else(args.next()),
-                                #[cfg(not(feature = "eq-separator"))]
-                                args.next(),
-                            )?
-                        }
-                    } else {
-                        // Not found
+                        Err(_) => (),
                     }
                 }
-                _ => {
-                    #[cfg(feature = "eq-separator")]
-                    let (name, eq_index) = match checked_arg.find('=') {
-                        Some(eq) => (&checked_arg[1..eq], Some(eq)),
-                        _ => (&checked_arg[1..], None),
-                    };
-                    #[cfg(all(
-                        feature = "single-hyphen-option-names",
-                        not(feature = "eq-separator"),
-                    ))]
-                    let name = &checked_arg[1..];
-                    #[cfg(feature = "single-hyphen-option-names")]
-                    if let Ok(op) =
-                        // router.options.iter().position(|mapper| {
-                        //     router.names[mapper.name as usize].as_bytes()
-                        //         [0]
-                        //         == name.as_bytes()[0]
-                        //         && router.names[mapper.name as usize]
-                        //             == name
-                        // })
-                        router.options.binary_search_by(|o| {
-                                router.names[o.name as usize].cmp(name)
-                            })
-                    {
-                        // Found
-                        if c.option_occurrences[op] == 0 {
-                            options_found += 1;
-                        }
-                        c.option_occurrences[op] += 1;
-                        if let OptArgKind::KeyOnly =
-                            router.options[op].kind
-                        {
-                        } else {
-                            add_found_option(
-                                op,
-                                router.options,
-                                &mut c,
-                                #[cfg(feature = "eq-separator")]
-                                eq_index
-                                    .and_then(|pos| {
-                                        Some(checked_arg[pos + 1..].into())
-                                    })
-                                    .or_else(args.next()),
-                                #[cfg(not(feature = "eq-separator"))]
-                                args.next(),
-                            )?
-                        }
-                        continue;
-                    }
-                    // Shorts
-                    for ch in chars {
-                        if let Some((o, _)) = router
-                            .short_option_mappers
-                            .iter()
-                            .find(|(_, mapper)| *mapper == ch)
-                        {
-                            if c.option_occurrences[*o as usize] == 0 {
-                                options_found += 1;
-                            }
-                            c.option_occurrences[*o as usize] += 1;
-                            if let OptArgKind::KeyOnly =
-                                router.options[*o as usize].kind
-                            {
-                            } else {
-                                // +2 for '-' + character
-                                if checked_arg.len() > 2 {
-                                    // Found an option that expects an option-arg,
-                                    // which maybe shouldn't be allowed in a group
-                                    return Err(io::Error::from(
-                                        io::ErrorKind::InvalidInput,
-                                    ));
-                                }
-                                add_found_option(
-                                    *o as usize,
-                                    router.options,
-                                    &mut c,
-                                    args.next(),
-                                )?;
-                            }
-                        } else {
-                            // Option not found
-                        }
+                if let Err(candidates) = &found {
+                    if candidates.len() > 1 {
+                        return Err(ParseError::Ambiguous {
+                            arg: format!("{}{}", OPTION_PREFIX, name)
+                                .into(),
+                            position,
+                            segment: c.selected,
+                            candidates: candidates.clone(),
+                        });
                     }
                 }
+                if let Ok(op) = found {
+                    add_option(
+                        &mut c,
+                        op,
+                        attached,
+                        || lexer.value(),
+                        &mut option_positions,
+                        position,
+                    )?;
+                    continue;
+                }
+                #[cfg(feature = "single-hyphen-option-names")]
+                if let Some(shorts) = long.shorts() {
+                    add_short_options(
+                        &mut c,
+                        &mut lexer,
+                        &shorts,
+                        &mut option_positions,
+                        position,
+                    )?;
+                    continue;
+                }
+                let e = ParseError::UnknownOption {
+                    arg: long.as_str().into(),
+                    position,
+                    segment: c.selected,
+                    suggestions: Vec::new(),
+                };
+                unknown_arg(&mut c, e)?;
+                continue;
             }
-            continue;
-        }
+            Token::Shorts(shorts) => {
+                add_short_options(
+                    &mut c,
+                    &mut lexer,
+                    &shorts,
+                    &mut option_positions,
+                    position,
+                )?;
+                continue;
+            }
+        };
+        let checked_arg = match arg.to_str() {
+            Some(a) => a,
+            _ => {
+                // Won't match any segment, since they're all UTF-8
+                if has_operands_left {
+                    add_operand(&mut c, arg)?;
+                } else {
+                    let e = unmatched_segment(&c, arg, position);
+                    unknown_arg(&mut c, e)?;
+                }
+                continue;
+            }
+        };
 
-        if router.segments[c.selected as usize].operands
-            != (c.operands.len() - c.path_params as usize) as u16
-        {
-            c.operands.push(arg);
+        if has_operands_left {
+            add_operand(&mut c, arg)?;
             continue;
         }
 
+        // Literal names are preferred over path parameters, which
+        // are only used when no sibling matches the argument
+        let parent = c.selected;
+        let mut param = None;
+        // Literal names the argument is a prefix of, with
+        // `Router::abbreviations()`
+        let mut abbreviated = Vec::new();
         while tree_index
-            < c.selected + router.tree[c.selected as usize].child_span + 1
+            < parent + router.tree[parent as usize].child_span + 1
         {
-            if router.names
-                [router.segments[tree_index as usize].name as usize]
-                .starts_with(':')
-            {
+            let name = router.names
+                [router.segments[tree_index as usize].name as usize];
+            if checked_arg == name {
                 c.selected = tree_index;
-                tree_index += 1;
-                c.path_params += 1;
-                c.operands.push(arg);
                 break;
             }
-            if checked_arg
-                == router.names
-                    [router.segments[tree_index as usize].name as usize]
+            if router.abbreviations
+                && !checked_arg.is_empty()
+                && name.starts_with(checked_arg)
+                && !name.starts_with([':', '*'])
             {
-                c.selected = tree_index;
-                tree_index += 1;
-                break;
+                abbreviated.push(tree_index);
+            }
+            if param.is_none()
+                && name.starts_with(':')
+                && !matches!(
+                    router.validators[tree_index as usize],
+                    Some(f) if f(&arg).is_err()
+                )
+            {
+                param = Some(tree_index);
             }
             // Skip to next sibling segment
             tree_index += router.tree[tree_index as usize].child_span + 1
         }
+        match (c.selected == parent, &abbreviated[..]) {
+            (true, &[segment]) => c.selected = segment,
+            (true, [_, _, ..]) => {
+                return Err(ParseError::Ambiguous {
+                    arg,
+                    position,
+                    segment: parent,
+                    candidates: abbreviated
+                        .iter()
+                        .map(|&s| {
+                            router.names
+                                [router.segments[s as usize].name as usize]
+                        })
+                        .collect(),
+                })
+            }
+            _ => (),
+        }
+        if let (true, Some(param)) = (c.selected == parent, param) {
+            c.selected = param;
+        }
+        if let (true, Some(rest)) = (c.selected == parent, catch_all) {
+            c.selected = rest;
+            events::record(&mut c, events::RawEvent::Segment(rest));
+            c.operands.push(arg);
+            c.operands.extend(lexer.map(Token::into_arg));
+            c.operands_end = c.operands.len() as u16;
+            break;
+        }
+        if c.selected != parent {
+            tree_index = c.selected + 1;
+            let selected = c.selected;
+            events::record(&mut c, events::RawEvent::Segment(selected));
+        }
+        if c.selected == parent {
+            let e = unmatched_segment(&c, arg, position);
+            unknown_arg(&mut c, e)?;
+        } else if router.names
+            [router.segments[c.selected as usize].name as usize]
+            .starts_with(':')
+        {
+            c.path_params += 1;
+            c.operands.push(arg);
+        }
+    }
+    // Without arguments left for them, an optional path parameter
+    // or catch-all is still selected, with no value
+    if let Some(child) = router.implicit_child(c.selected) {
+        c.selected = child;
+    }
+    if !c.placements.is_empty() {
+        place_options(&mut c)?;
+    }
+    // Precedence is command line, then environment, then default,
+    // for the options the selected segment allows
+    let allows_any = router.segments[c.selected as usize].opt_groups == 0;
+    let mut fallbacks = Vec::new();
+    for (i, opt) in router.options.iter().enumerate() {
+        if c.option_occurrences[i] > 0
+            || (opt.env.is_none() && opt.default.is_none())
+            || !(allows_any
+                || router
+                    .segment_opt_groups(c.selected)
+                    .any(|g| router.opt_groups[g].contains(&(i as u16))))
+        {
+            continue;
+        }
+        let from_env =
+            opt.env.and_then(std::env::var_os).filter(|v| !v.is_empty());
+        let env = from_env.is_some().then_some(opt.env).flatten();
+        let fallback =
+            from_env.or_else(|| opt.default.map(OsString::from));
+        if let Some(fallback) = fallback {
+            fallbacks.push(i as u16);
+            // It wasn't given, so the error names where it's from
+            // instead of a position
+            add_option_arg(i, &mut c, fallback, 0).map_err(
+                |e| match e {
+                    ParseError::Conversion {
+                        option,
+                        name,
+                        arg,
+                        reason,
+                    } => ParseError::InvalidFallback {
+                        option,
+                        name,
+                        env,
+                        arg,
+                        reason,
+                    },
+                    e => e,
+                },
+            )?;
+        }
     }
     c.saved_args.shrink_to_fit();
     c.option_args.shrink_to_fit();
@@ -808,60 +2130,88 @@ pub fn parse_cli_route(
         // No terminator was found, so this wasn't set
         c.operands_end = c.operands.len() as u16;
     }
-
-    let groups = router.segments[c.selected as usize].opt_groups >> 12;
-    if groups == 0 {
-        return Ok(c);
+    let segment = router.segments[c.selected as usize];
+    let found = c.operands().len() as u16;
+    if found < segment.min_operands {
+        return Err(ParseError::MissingOperands {
+            segment: c.selected,
+            segment_name: router.names[segment.name as usize],
+            min: segment.min_operands,
+            found,
+        });
     }
-    let index = router.segments[c.selected as usize].opt_groups << 4 >> 4;
-    // println!("groups: {}, index: {}", groups, index);
-    let groups =
-        &router.opt_groups[index as usize..(index + groups) as usize];
-
-    let mut found_opt = None;
-    let mut group_options_found = 0u16;
-    for (idx, grp) in groups.iter().enumerate() {
-        for o in *grp {
-            if c.option_occurrences[*o as usize] > 0 {
-                found_opt = Some(*o);
-                group_options_found += 1;
-                if router.opt_group_rules[index as usize + idx] as u8
-                    & OptGroupRules::OneOf as u8
-                    != 0
-                {
-                    if let Some(found) = found_opt {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidInput,
-                            format!(
-                                "These options are mutually exclusive: {}, {}",
-                                router.names[router.options[found as usize].name as usize],
-                                router.names[router.options[*o as usize].name as usize]
-                            )
-                        ));
-                    }
-                    continue;
+
+    for g in router.segment_opt_groups(c.selected) {
+        let (rules, grp) =
+            (router.opt_group_rules[g], router.opt_groups[g]);
+        let mut found_opt = None;
+        for o in grp {
+            if c.option_occurrences[*o as usize] == 0 {
+                continue;
+            }
+            if let Some(found) = found_opt {
+                if rules & OptGroupRules::OneOf as u8 != 0 {
+                    return Err(ParseError::Conflict {
+                        options: (found, *o),
+                        names: (
+                            router.names[router.options[found as usize]
+                                .name
+                                as usize],
+                            router.names[router.options[*o as usize].name
+                                as usize],
+                        ),
+                        segment: c.selected,
+                    });
                 }
-                break;
+                continue;
             }
+            found_opt = Some(*o);
         }
+        // A fallback value satisfies a required group, without
+        // conflicting with what's given
         if found_opt.is_none()
-            && router.opt_group_rules[index as usize + idx] as u8
-                & OptGroupRules::Required as u8
-                != 0
+            && rules & OptGroupRules::Required as u8 != 0
+            && !grp.iter().any(|o| fallbacks.contains(o))
         {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Missing a required option.",
-            ));
-        }
-    }
-    // TODO: This might be wrong as I hit it in benchmark app with
-    //       cargo run -- --width 43 --number 2 --number 5
-    if group_options_found != options_found {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!("Invalid options: {}", group_options_found),
-        ));
+            return Err(ParseError::MissingRequired {
+                options: grp,
+                names: grp
+                    .iter()
+                    .map(|o| {
+                        router.names
+                            [router.options[*o as usize].name as usize]
+                    })
+                    .collect(),
+                segment: c.selected,
+            });
+        }
+    }
+    // Options given that none of the groups allow. A segment
+    // without groups of its own allows any option, even when it
+    // inherits groups
+    if router.segments[c.selected as usize].opt_groups == 0 {
+        return Ok(c);
+    }
+    if let Some(o) =
+        c.option_occurrences
+            .iter()
+            .enumerate()
+            .position(|(o, count)| {
+                *count > 0
+                    && !router.segment_opt_groups(c.selected).any(|g| {
+                        router.opt_groups[g].contains(&(o as u16))
+                    })
+            })
+    {
+        let mut arg = OsString::from(OPTION_PREFIX);
+        arg.push(router.names[router.options[o].name as usize]);
+        let e = ParseError::UnknownOption {
+            arg,
+            position: option_positions[o],
+            segment: c.selected,
+            suggestions: Vec::new(),
+        };
+        return Err(add_suggestions(&c, e));
     }
     Ok(c)
 }
@@ -902,6 +2252,9 @@ mod tests {
     fn b1_action(_: Context) -> io::Result<()> {
         Ok(println!("path command"))
     }
+    fn arg(a: &str) -> OsString {
+        OsString::from(a)
+    }
 
     fn data() -> Router {
         Router {
@@ -955,48 +2308,66 @@ mod tests {
             segments: &[
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 3,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 4,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 1 << 12,
                     name: 5,
+                    isolated: false,
                 },
                 Segment {
                     operands: 2,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 6,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 7,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 8,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 1 << 12 | 1,
                     name: 9,
+                    isolated: false,
                 },
                 Segment {
                     operands: 1,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 10,
+                    isolated: false,
                 },
                 Segment {
                     operands: 0,
+                    min_operands: 0,
                     opt_groups: 0,
                     name: 11,
+                    isolated: false,
                 },
             ],
             actions: &[
@@ -1010,6 +2381,8 @@ mod tests {
                 |_| Ok(println!("c help")),
                 |_| Ok(println!("d help")),
             ],
+            validators: &[None; 9],
+            named_operands: &[&[] as &[Operand]; 9],
             short_option_mappers: &[(0, 'k'), (1, 'm'), (2, 's')],
             names: &[
                 "key-only", "multi1", "single1", "path", "a", "a1", "a2",
@@ -1019,14 +2392,44 @@ mod tests {
                 Opt {
                     kind: OptArgKind::KeyOnly,
                     name: 0,
+                    validator: None,
+                    default: None,
+                    env: None,
+                    repeat: Repeat::Last,
+                    delimiter: None,
+                    implicit: None,
+                    value_name: None,
+                    element_validators: &[],
+                    possible_values: &[],
+                    chars: None,
                 },
                 Opt {
                     kind: OptArgKind::Multiple,
                     name: 1,
+                    validator: None,
+                    default: None,
+                    env: None,
+                    repeat: Repeat::Last,
+                    delimiter: None,
+                    implicit: None,
+                    value_name: None,
+                    element_validators: &[],
+                    possible_values: &[],
+                    chars: None,
                 },
                 Opt {
                     kind: OptArgKind::Single,
                     name: 2,
+                    validator: None,
+                    default: None,
+                    env: None,
+                    repeat: Repeat::Last,
+                    delimiter: None,
+                    implicit: None,
+                    value_name: None,
+                    element_validators: &[],
+                    possible_values: &[],
+                    chars: None,
                 },
             ],
             opt_group_rules: &[
@@ -1035,6 +2438,11 @@ mod tests {
             ],
             opt_groups: &[&[1, 2], &[0]],
             help_opt_index: None,
+            unknown_args: UnknownArgs::Ignore,
+            option_placement: OptionPlacement::Anywhere,
+            record_events: false,
+            response_file_depth: 0,
+            abbreviations: false,
         }
     }
 
@@ -1093,7 +2501,7 @@ mod tests {
             )
             .unwrap();
             assert_eq!(c.selected, 5);
-            assert_eq!(c.option_occurrences, [0, 1, 0]);
+            assert_eq!(c.option_occurrences, [0, 0, 1]);
             assert_eq!(c.saved_args, vec![OsString::from("val")]);
 
             // TODO: Handle "-=" and "-=val" case
@@ -1117,8 +2525,8 @@ mod tests {
             assert_eq!(c.saved_args.len(), 0);
         }
 
-        // * Prohibit an option from clustering when it expects
-        // * an option-arg
+        // * An option in a cluster that expects an option-arg
+        // * takes the rest of the cluster
         let c = parse_cli_route(
             &router,
             vec![
@@ -1126,8 +2534,51 @@ mod tests {
                 OsString::from("-skm"),
                 OsString::from("b1"),
             ],
+        )
+        .unwrap();
+        assert_eq!(c.selected, 5);
+        assert_eq!(c.option_occurrences, [0, 0, 1]);
+        assert_eq!(c.saved_args, vec![OsString::from("km")]);
+
+        // * Or the next arg when it ends the cluster
+        let c = parse_cli_route(
+            &router,
+            vec![
+                OsString::from("b"),
+                OsString::from("-ks"),
+                OsString::from("val"),
+                OsString::from("b1"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(c.selected, 5);
+        assert_eq!(c.option_occurrences, [1, 0, 1]);
+        assert_eq!(c.saved_args, vec![OsString::from("val")]);
+
+        // * Fail only when there's no option-arg at all
+        let c = parse_cli_route(
+            &router,
+            vec![OsString::from("b"), OsString::from("-ks")],
         );
-        assert!(c.is_err());
+        assert!(matches!(
+            c,
+            Err(ParseError::MissingArgument { option: 2, .. })
+        ));
+
+        // * An attached option-arg separated by an '=' character
+        let c = parse_cli_route(
+            &router,
+            vec![
+                OsString::from("b"),
+                OsString::from("-s=val"),
+                OsString::from("b1"),
+            ],
+        )
+        .unwrap();
+        #[cfg(feature = "eq-separator")]
+        assert_eq!(c.saved_args, vec![OsString::from("val")]);
+        #[cfg(not(feature = "eq-separator"))]
+        assert_eq!(c.saved_args, vec![OsString::from("=val")]);
 
         // * An option that expects an option-arg
         let c = parse_cli_route(
@@ -1339,6 +2790,1341 @@ mod tests {
         .is_ok());
     }
     #[test]
+    fn should_return_structured_parse_errors() {
+        let router = data();
+
+        match parse_cli_route(
+            &router,
+            vec![
+                OsString::from("b"),
+                OsString::from("b1"),
+                option_name!("single1"),
+            ],
+        ) {
+            Err(ParseError::MissingArgument {
+                option, position, ..
+            }) => {
+                assert_eq!(option, 2);
+                assert_eq!(position, 2);
+            }
+            _ => panic!("Expected a missing option-argument"),
+        }
+
+        match parse_cli_route(
+            &router,
+            vec![
+                OsString::from("a"),
+                option_name!("key-only"),
+                OsString::from("a1"),
+            ],
+        ) {
+            Err(ParseError::UnknownOption {
+                arg,
+                position,
+                segment,
+                suggestions,
+            }) => {
+                assert_eq!(arg, option_name!("key-only"));
+                assert_eq!(position, 1);
+                assert_eq!(segment, 2);
+                assert!(suggestions.is_empty());
+            }
+            _ => panic!("Expected an option the groups don't allow"),
+        }
+
+        match parse_cli_route(
+            &router,
+            vec![OsString::from("b"), OsString::from("b2")],
+        ) {
+            Err(e @ ParseError::MissingRequired { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "Missing a required option: {}key-only",
+                        OPTION_PREFIX
+                    )
+                );
+                assert_eq!(
+                    io::Error::from(e).kind(),
+                    io::ErrorKind::InvalidInput
+                );
+            }
+            _ => panic!("Expected a missing required option"),
+        }
+
+        let c = parse_cli_route(
+            &router,
+            vec![
+                OsString::from("a"),
+                OsString::from("a1"),
+                option_name!("single1"),
+                OsString::from("NaN"),
+            ],
+        )
+        .unwrap();
+        match c.opt(2usize).value::<u8>() {
+            Err(ParseError::Conversion { option, arg, .. }) => {
+                assert_eq!(option, 2);
+                assert_eq!(arg, OsString::from("NaN"));
+            }
+            _ => panic!("Expected a conversion failure"),
+        }
+    }
+    #[test]
+    fn should_handle_unknown_args_by_policy() {
+        let args = vec![
+            OsString::from("b"),
+            OsString::from("--verbos"),
+            OsString::from("b1"),
+            OsString::from("extra"),
+        ];
+        let router = data();
+        let c = parse_cli_route(&router, args.clone()).unwrap();
+        assert_eq!(c.selected, 5);
+        assert!(c.unknown_args().is_empty());
+
+        let router = data().unknown_args(UnknownArgs::Error);
+        match parse_cli_route(&router, args.clone()) {
+            Err(ParseError::UnknownOption { arg, position, .. }) => {
+                assert_eq!(arg, OsString::from("--verbos"));
+                assert_eq!(position, 1);
+            }
+            _ => panic!("Expected an unknown option"),
+        }
+        assert!(matches!(
+            parse_cli_route(
+                &router,
+                vec![OsString::from("b"), OsString::from("b3")]
+            ),
+            Err(ParseError::UnknownSegment { segment: 4, .. })
+        ));
+        assert!(matches!(
+            parse_cli_route(
+                &router,
+                vec![
+                    OsString::from("b"),
+                    OsString::from("b1"),
+                    OsString::from("extra")
+                ]
+            ),
+            Err(ParseError::TooManyOperands {
+                segment: 5,
+                position: 2,
+                ..
+            })
+        ));
+
+        match parse_cli_route(
+            &router,
+            vec![OsString::from("b"), OsString::from("b3")],
+        ) {
+            Err(e @ ParseError::UnknownSegment { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "Unrecognized command: b3\n  Did you mean b1, b2?"
+                );
+            }
+            _ => panic!("Expected an unknown segment"),
+        }
+        match parse_cli_route(
+            &router,
+            vec![
+                OsString::from("a"),
+                OsString::from("a1"),
+                OsString::from("--singel1"),
+            ],
+        ) {
+            Err(ParseError::UnknownOption { suggestions, .. }) => {
+                assert_eq!(suggestions, ["single1"]);
+            }
+            _ => panic!("Expected an unknown option"),
+        }
+
+        let router = data().unknown_args(UnknownArgs::Collect);
+        let c = parse_cli_route(&router, args).unwrap();
+        assert_eq!(c.selected, 5);
+        assert_eq!(
+            c.unknown_args(),
+            [OsString::from("--verbos"), OsString::from("extra")]
+        );
+    }
+    #[test]
+    fn should_convert_option_args_without_requiring_utf8() {
+        let router = data();
+        let c = parse_cli_route(
+            &router,
+            vec![
+                OsString::from("a"),
+                OsString::from("a1"),
+                option_name!("single1"),
+                OsString::from("40"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(c.opt(2usize).value_os::<u16>().unwrap(), Some(40));
+        assert_eq!(
+            c.opt(2usize).path().unwrap(),
+            Some(PathBuf::from("40"))
+        );
+        assert_eq!(c.opt(1usize).path().unwrap(), None);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+
+            let invalid = OsString::from_vec(vec![b'a', 0xff, b'b']);
+            let c = parse_cli_route(
+                &router,
+                vec![
+                    OsString::from("a"),
+                    OsString::from("a1"),
+                    option_name!("single1"),
+                    invalid.clone(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                c.opt(2usize).path().unwrap(),
+                Some(PathBuf::from(invalid.clone()))
+            );
+            assert_eq!(
+                c.opt(2usize).value_os::<OsString>().unwrap(),
+                Some(invalid.clone())
+            );
+            match c.opt(2usize).value_os::<String>() {
+                Err(e @ ParseError::Conversion { .. }) => assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "Invalid value \"a\u{FFFD}b\" for {}single1: invalid UTF-8",
+                        OPTION_PREFIX
+                    )
+                ),
+                _ => panic!("Expected a conversion failure"),
+            }
+        }
+    }
+    #[test]
+    fn should_validate_and_get_typed_options_from_optmap() {
+        optmap!(enum O using [
+          Quiet,
+          Tags > String[],
+          Width | 'w' > u16,
+        ]);
+        const C: Seg = Seg::new("prog").nest(&[Seg::new("print")]);
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(vec![
+                OsString::from("print"),
+                option_name!("width"),
+                OsString::from("80"),
+                option_name!("tags"),
+                OsString::from("a"),
+                option_name!("tags"),
+                OsString::from("b"),
+            ])
+            .unwrap();
+        assert_eq!(O::width(&c).unwrap(), Some(80));
+        assert_eq!(O::tags(&c).unwrap(), ["a", "b"]);
+
+        let c = R.parse(vec![OsString::from("print")]).unwrap();
+        assert_eq!(O::width(&c).unwrap(), None);
+        assert!(O::tags(&c).unwrap().is_empty());
+
+        match R.parse(vec![
+            OsString::from("print"),
+            OsString::from("-w"),
+            OsString::from("abc"),
+        ]) {
+            Err(ParseError::Conversion { option, arg, .. }) => {
+                assert_eq!(option, O::Width as u16);
+                assert_eq!(arg, OsString::from("abc"));
+            }
+            _ => panic!("Expected a conversion failure while parsing"),
+        }
+    }
+    #[test]
+    fn should_derive_a_struct_from_a_context() {
+        optmap!(enum O using [
+          Number > i32,
+          Quiet,
+          Tags > String[],
+          Width > u16,
+        ]);
+        #[derive(FromContext)]
+        struct AppArgs {
+            #[opt(O::Quiet)]
+            quiet: bool,
+            #[opt(O::Width)]
+            width: Option<u16>,
+            #[opt(O::Number)]
+            number: i32,
+            #[opt(O::Tags)]
+            tags: Vec<String>,
+            /// The user's id
+            #[path_param("id")]
+            id: u32,
+            #[operands]
+            input: Vec<PathBuf>,
+            #[operand(1)]
+            second: Option<String>,
+        }
+        const C: Seg = Seg::new("prog")
+            .nest(&[Seg::new(":id").operands(0..=u16::MAX)]);
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(vec![
+                OsString::from("7"),
+                option_name!("number"),
+                OsString::from("-3"),
+                option_name!("quiet"),
+                OsString::from("a.txt"),
+                OsString::from("b.txt"),
+            ])
+            .unwrap();
+        let args = AppArgs::try_from(&c).unwrap();
+        assert!(args.quiet);
+        assert_eq!(args.width, None);
+        assert_eq!(args.number, -3);
+        assert!(args.tags.is_empty());
+        assert_eq!(args.id, 7);
+        assert_eq!(args.input, [PathBuf::from("a.txt"), "b.txt".into()]);
+        assert_eq!(args.second.as_deref(), Some("b.txt"));
+
+        let c = R.parse(vec![OsString::from("seven")]).unwrap();
+        let errors = AppArgs::try_from(&c).err().unwrap();
+        assert_eq!(
+            errors.0.iter().map(|e| e.field).collect::<Vec<_>>(),
+            ["number", "id"]
+        );
+        assert_eq!(
+            errors.to_string(),
+            "number: Missing a required option\nid: Invalid value \"seven\": invalid digit found in string"
+        );
+
+        // * Fields can have the names the generated code uses
+        #[derive(FromContext)]
+        struct Shadowing {
+            #[opt(O::Width)]
+            c: Option<u16>,
+            #[opt(O::Tags)]
+            errors: Vec<String>,
+        }
+        let c = R
+            .parse(vec![
+                OsString::from("7"),
+                option_name!("width"),
+                OsString::from("80"),
+                option_name!("tags"),
+                OsString::from("a"),
+            ])
+            .unwrap();
+        let args = Shadowing::try_from(&c).unwrap();
+        assert_eq!(args.c, Some(80));
+        assert_eq!(args.errors, ["a"]);
+    }
+    #[test]
+    fn should_get_named_and_typed_operands() {
+        optmap!(enum O using [Quiet,]);
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new("cp").named_operands(&[
+                Operand::new("dst"),
+                Operand::new("src").many(),
+            ]),
+            Seg::new("wait").named_operands(&[
+                Operand::new("seconds").validator(parses::<u32>)
+            ]),
+        ]);
+        const R: Router = router!(O, C);
+        let args = |args: &[&str]| {
+            args.iter().map(OsString::from).collect::<Vec<_>>()
+        };
+
+        let c = R.parse(args(&["cp", "out", "a", "b"])).unwrap();
+        assert_eq!(c.operand("dst"), Some(&OsString::from("out")));
+        assert_eq!(c.operand("src"), Some(&OsString::from("a")));
+        assert_eq!(c.named_operand("src"), args(&["a", "b"]));
+        assert_eq!(c.operand("other"), None);
+        assert!(matches!(
+            R.parse(args(&["cp", "out"])),
+            Err(ParseError::MissingOperands {
+                min: 2,
+                found: 1,
+                ..
+            })
+        ));
+
+        let c = R.parse(args(&["wait", "5"])).unwrap();
+        assert_eq!(c.operand_value::<u32>("seconds").unwrap(), Some(5));
+        match R.parse(args(&["wait", "soon"])) {
+            Err(
+                e @ ParseError::InvalidOperand {
+                    name: "seconds", ..
+                },
+            ) => {
+                assert_eq!(
+                    e.to_string(),
+                    "Invalid value \"soon\" for <seconds>: invalid digit found in string"
+                );
+            }
+            _ => panic!("Expected an invalid operand"),
+        }
+
+        #[derive(FromContext)]
+        struct CpArgs {
+            #[operand("dst")]
+            dst: PathBuf,
+            #[operand("src")]
+            src: Vec<PathBuf>,
+        }
+        let c = R.parse(args(&["cp", "out", "a", "b"])).unwrap();
+        let cp = CpArgs::try_from(&c).unwrap();
+        assert_eq!(cp.dst, PathBuf::from("out"));
+        assert_eq!(cp.src, [PathBuf::from("a"), "b".into()]);
+    }
+    #[test]
+    fn should_match_unambiguous_abbreviations() {
+        optmap!(enum O using [
+          Verbose,
+          Version,
+          Width > u16,
+        ]);
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new("start"),
+            Seg::new("stat"),
+            Seg::new("status"),
+            Seg::new("stop"),
+            Seg::new("list").nest(&[Seg::new(":name"), Seg::new("all")]),
+        ]);
+        const R: Router = router!(O, C).abbreviations();
+        let option = |name: &str| {
+            let mut a = OsString::from(OPTION_PREFIX);
+            a.push(name);
+            a
+        };
+
+        let c = R
+            .parse(vec![
+                arg("star"),
+                option("wid"),
+                arg("80"),
+                option("verb"),
+            ])
+            .unwrap();
+        assert_eq!(c.selected, 1);
+        assert_eq!(O::width(&c).unwrap(), Some(80));
+        assert_eq!(c.option_occurrences(O::Verbose), 1);
+        // * An exact name is matched before a longer one
+        assert_eq!(R.parse(vec![arg("stat")]).unwrap().selected, 2);
+        // * An abbreviation is matched before a path parameter
+        let c = R.parse(vec![arg("li"), arg("a")]).unwrap();
+        assert_eq!(c.selected, 7);
+        assert!(c.path_params().is_empty());
+        assert_eq!(R.parse(vec![arg("l"), arg("b")]).unwrap().selected, 6);
+
+        // * Also when the prefix has an attached value
+        for given in ["ver", "ver=x"] {
+            match R.parse(vec![option(given)]) {
+                Err(e @ ParseError::Ambiguous { .. }) => assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "{p}ver is ambiguous: {p}verbose, {p}version",
+                        p = OPTION_PREFIX
+                    )
+                ),
+                _ => panic!("Expected an ambiguous option"),
+            }
+        }
+        match R.parse(vec![arg("st")]) {
+            Err(e @ ParseError::Ambiguous { segment: 0, .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "st is ambiguous: start, stat, status, stop"
+                )
+            }
+            _ => panic!("Expected an ambiguous segment"),
+        }
+
+        // * Without the setting, prefixes aren't names
+        const A: Router = router!(O, C).unknown_args(UnknownArgs::Error);
+        assert!(matches!(
+            A.parse(vec![option("verb")]),
+            Err(ParseError::UnknownOption { .. })
+        ));
+    }
+    #[test]
+    fn should_get_path_params_by_name() {
+        const C: Seg =
+            Seg::new("prog").nest(&[Seg::new("repo").nest(&[Seg::new(
+                ":owner",
+            )
+            .nest(&[Seg::new(":name").nest(&[
+                Seg::new("issues").nest(&[Seg::new(":number")])
+            ])])])]);
+        optmap!(enum O using [Quiet,]);
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(
+                ["repo", "rust-lang", "rust", "issues", "42"]
+                    .map(OsString::from)
+                    .to_vec(),
+            )
+            .unwrap();
+        assert_eq!(c.path_param("owner").unwrap(), "rust-lang");
+        assert_eq!(c.path_param(":name").unwrap(), "rust");
+        assert_eq!(c.path_param_value::<u32>("number").unwrap(), Some(42));
+        assert_eq!(c.path_param("missing"), None);
+
+        let c = R
+            .parse(["repo", "a", "b"].map(OsString::from).to_vec())
+            .unwrap();
+        assert_eq!(c.path_param("name").unwrap(), "b");
+        assert_eq!(c.path_param("number"), None);
+
+        let c = R
+            .parse(
+                ["repo", "a", "b", "issues", "x"]
+                    .map(OsString::from)
+                    .to_vec(),
+            )
+            .unwrap();
+        assert!(matches!(
+            c.path_param_value::<u32>("number"),
+            Err(ParseError::InvalidPathParam {
+                name: ":number",
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn should_match_path_params_by_type_after_literals() {
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new(":port").validator(parses::<u16>),
+            Seg::new(":host"),
+            Seg::new("status"),
+        ]);
+        optmap!(enum O using [Quiet,]);
+        const R: Router = router!(O, C).unknown_args(UnknownArgs::Error);
+        let selected = |arg: &str| {
+            let c = R.parse(vec![OsString::from(arg)]).unwrap();
+            R.names[R.segments[c.selected as usize].name as usize]
+        };
+
+        assert_eq!(selected("status"), "status");
+        assert_eq!(selected("8080"), ":port");
+        assert_eq!(selected("99999"), ":host");
+        assert_eq!(selected("localhost"), ":host");
+
+        const STRICT: Seg = Seg::new("prog")
+            .nest(&[Seg::new(":port").validator(parses::<u16>)]);
+        const S: Router =
+            router!(O, STRICT).unknown_args(UnknownArgs::Error);
+        assert!(matches!(
+            S.parse(vec![OsString::from("http")]),
+            Err(ParseError::UnknownSegment { .. })
+        ));
+    }
+    #[test]
+    fn should_check_operand_ranges() {
+        optmap!(enum O using [Quiet,]);
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new("cp").operands(2..=3),
+            Seg::new("cat").operands(1..=1),
+            Seg::new("rm").operands(1..=1),
+            Seg::new("get").nest(&[Seg::new(":id")]),
+            Seg::new("ls"),
+        ]);
+        const R: Router = router!(O, C);
+        let args = |args: &[&str]| {
+            args.iter().map(OsString::from).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            R.parse(args(&["cp", "a", "b"])).unwrap().operands().len(),
+            2
+        );
+        assert!(R.parse(args(&["cp", "a", "b", "c"])).is_ok());
+        match R.parse(args(&["cp", "a"])) {
+            Err(e @ ParseError::MissingOperands { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "cp expects at least 2 operands, but got 1"
+                );
+            }
+            _ => panic!("Expected too few operands"),
+        }
+        // Extra operands are an error, even when unknown args are
+        // ignored
+        match R.parse(args(&["cp", "a", "b", "c", "d"])) {
+            Err(e @ ParseError::TooManyOperands { segment: 1, .. }) => {
+                assert_eq!(e.to_string(), "Unexpected operand for cp: d");
+            }
+            _ => panic!("Expected too many operands"),
+        }
+        // * `-` is an operand like any other
+        assert_eq!(R.parse(args(&["rm", "-"])).unwrap().operands(), ["-"]);
+        match R.parse(args(&["rm", "x", "-"])) {
+            Err(e @ ParseError::TooManyOperands { segment: 3, .. }) => {
+                assert_eq!(e.to_string(), "Unexpected operand for rm: -");
+            }
+            _ => panic!("Expected too many operands"),
+        }
+        // * It's never a path parameter, or dropped when there's
+        //   no room for it
+        for (route, segment) in [(["get", "-"], 4), (["ls", "-"], 6)] {
+            match R.parse(args(&route)) {
+                Err(ParseError::TooManyOperands {
+                    segment: s,
+                    arg,
+                    ..
+                }) => {
+                    assert_eq!((s, arg), (segment, OsString::from("-")));
+                }
+                _ => panic!("Expected too many operands"),
+            }
+        }
+
+        // * An exact count is checked too
+        match R.parse(args(&["cat"])) {
+            Err(e @ ParseError::MissingOperands { segment: 2, .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "cat expects at least 1 operand, but got 0"
+                );
+            }
+            _ => panic!("Expected too few operands"),
+        }
+        match R.parse(args(&["cat", "a", "b"])) {
+            Err(e @ ParseError::TooManyOperands { segment: 2, .. }) => {
+                assert_eq!(e.to_string(), "Unexpected operand for cat: b");
+            }
+            _ => panic!("Expected too many operands"),
+        }
+    }
+    #[test]
+    fn should_match_optional_and_catch_all_path_params() {
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new("get").nest(&[Seg::new(":id?")]),
+            Seg::new("ext").nest(&[Seg::new("list"), Seg::new("*rest")]),
+        ]);
+        optmap!(enum O using [Quiet,]);
+        const R: Router = router!(O, C);
+        let parse = |args: &[&str]| {
+            R.parse(args.iter().map(OsString::from).collect::<Vec<_>>())
+                .unwrap()
+        };
+        let name = |c: &Context| {
+            R.names[R.segments[c.selected as usize].name as usize]
+        };
+
+        let c = parse(&["get", "7"]);
+        assert_eq!(name(&c), ":id?");
+        assert_eq!(c.path_param("id").unwrap(), "7");
+        let c = parse(&["get"]);
+        assert_eq!(name(&c), ":id?");
+        assert_eq!(c.path_param("id"), None);
+
+        let c = parse(&["ext", "build", "--release", "--", "x"]);
+        assert_eq!(name(&c), "*rest");
+        assert_eq!(c.rest(), ["build", "--release", "--", "x"]);
+        assert!(c.terminated_args().is_empty());
+        assert_eq!(c.option_occurrences(O::Quiet), 0);
+        let c = parse(&["ext", "--quiet"]);
+        assert_eq!(c.rest(), ["--quiet"]);
+        // * Help is swallowed too
+        let c = parse(&["ext", "run", "--quiet", "--help"]);
+        assert_eq!(c.rest(), ["run", "--quiet", "--help"]);
+        assert_eq!(c.option_occurrences(O::Quiet), 0);
+        let c = parse(&["ext"]);
+        assert_eq!(name(&c), "*rest");
+        assert!(c.rest().is_empty());
+        let c = parse(&["ext", "list"]);
+        assert_eq!(name(&c), "list");
+        assert!(c.rest().is_empty());
+    }
+    #[test]
+    fn should_inherit_cascading_option_groups() {
+        optmap!(enum O using [
+          Force,
+          Quiet,
+          Verbose,
+        ]);
+        const C: Seg = Seg::new("prog")
+            .options(&[OptGroup::oneof(&[O::Quiet, O::Verbose]).cascade()])
+            .nest(&[Seg::new("remote").nest(&[
+                Seg::new("add").options(&[OptGroup::anyof(&[O::Force])]),
+                Seg::new("raw")
+                    .isolated()
+                    .options(&[OptGroup::anyof(&[O::Force])]),
+            ])]);
+        const R: Router = router!(O, C);
+        let (force, quiet, verbose) = (
+            option_name!("force"),
+            option_name!("quiet"),
+            option_name!("verbose"),
+        );
+        let parse = |args: &[&OsString]| {
+            R.parse(args.iter().map(|&a| a.clone()).collect::<Vec<_>>())
+        };
+        let (remote, add, raw) = (
+            OsString::from("remote"),
+            OsString::from("add"),
+            OsString::from("raw"),
+        );
+
+        let c = parse(&[&remote, &add, &verbose, &force]).unwrap();
+        assert_eq!(c.option_occurrences(O::Verbose), 1);
+        assert!(matches!(
+            parse(&[&remote, &add, &verbose, &quiet]),
+            Err(ParseError::Conflict { .. })
+        ));
+        assert!(matches!(
+            parse(&[&remote, &raw, &verbose]),
+            Err(ParseError::UnknownOption { .. })
+        ));
+        assert!(parse(&[&remote, &verbose, &force]).is_ok());
+    }
+    #[test]
+    fn should_record_the_segment_options_are_given_at() {
+        optmap!(enum O using [
+          Config > String,
+          Force,
+          Verbose,
+        ]);
+        const C: Seg = Seg::new("prog")
+            .options(
+                &[OptGroup::anyof(&[O::Config, O::Verbose]).cascade()],
+            )
+            .nest(&[Seg::new("sub")
+                .options(&[OptGroup::anyof(&[O::Config, O::Force])])]);
+        let args = |args: &[&OsString]| {
+            args.iter().map(|&a| a.clone()).collect::<Vec<_>>()
+        };
+        let (config, force, verbose, sub) = (
+            option_name!("config"),
+            option_name!("force"),
+            option_name!("verbose"),
+            OsString::from("sub"),
+        );
+        let (a, b) = (OsString::from("a.toml"), OsString::from("b.toml"));
+
+        const R: Router =
+            router!(O, C).option_placement(OptionPlacement::Record);
+        let c = R
+            .parse(args(&[&config, &a, &verbose, &sub, &config, &b]))
+            .unwrap();
+        assert_eq!(
+            c.opt(O::Config).value::<String>().unwrap().unwrap(),
+            "b.toml"
+        );
+        assert_eq!(
+            c.opt_at(0, O::Config).value::<String>().unwrap().unwrap(),
+            "a.toml"
+        );
+        assert_eq!(
+            c.opt_at(1, O::Config).value::<String>().unwrap().unwrap(),
+            "b.toml"
+        );
+        assert_eq!(c.option_occurrences_at(0, O::Verbose), 1);
+        assert_eq!(c.option_occurrences_at(1, O::Verbose), 0);
+
+        // * Without recording, there's nothing at any segment
+        const A: Router = router!(O, C);
+        let c = A.parse(args(&[&config, &a, &sub])).unwrap();
+        assert_eq!(
+            c.opt_at(0, O::Config).value::<String>().unwrap(),
+            None
+        );
+
+        const S: Router =
+            router!(O, C).option_placement(OptionPlacement::Strict);
+        assert!(S.parse(args(&[&config, &a, &sub, &force])).is_ok());
+        match S.parse(args(&[&force, &sub])) {
+            Err(e @ ParseError::MisplacedOption { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "{}force must be given after sub",
+                        OPTION_PREFIX
+                    )
+                );
+            }
+            _ => panic!("Expected an option given before its segment"),
+        }
+    }
+    #[test]
+    fn should_list_events_in_the_order_they_were_given() {
+        optmap!(enum O using [
+          Force,
+          Output > String,
+        ]);
+        const C: Seg =
+            Seg::new("prog")
+                .nest(&[Seg::new("get")
+                    .nest(&[Seg::new(":id").operands(1..=2)])]);
+        const R: Router = router!(O, C).record_events();
+        let (force, output) =
+            (option_name!("force"), option_name!("output"));
+        let c = R
+            .parse(vec![
+                arg("get"),
+                force.clone(),
+                arg("7"),
+                arg("a"),
+                output.clone(),
+                arg("out.txt"),
+                arg("b"),
+                arg("--"),
+                arg("c"),
+            ])
+            .unwrap();
+        assert_eq!(
+            c.events(),
+            [
+                Event::Segment(1),
+                Event::Option {
+                    option: O::Force as u16,
+                    value: None
+                },
+                Event::PathParam {
+                    segment: 2,
+                    value: &arg("7")
+                },
+                Event::Operand(&arg("a")),
+                Event::Option {
+                    option: O::Output as u16,
+                    value: Some(&arg("out.txt"))
+                },
+                Event::Operand(&arg("b")),
+                Event::Terminator,
+                Event::Operand(&arg("c")),
+            ]
+        );
+
+        // * Without recording, there are only operands
+        const A: Router = router!(O, C);
+        let c = A
+            .parse(vec![arg("get"), force, arg("7"), arg("a")])
+            .unwrap();
+        assert_eq!(c.events(), [Event::Operand(&arg("a"))]);
+    }
+    #[test]
+    fn should_apply_repeat_policies() {
+        optmap!(enum O using [
+          #[repeat = "first"]
+          First > String,
+          Last > String,
+          #[repeat = "error"]
+          Once > String,
+          Quiet | 'q',
+          #[repeat = "max 3"]
+          Verbose | 'v',
+        ]);
+        const C: Seg = Seg::new("prog").operands(0..=1);
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(vec![
+                option_name!("first"),
+                arg("a"),
+                option_name!("first"),
+                arg("b"),
+                option_name!("last"),
+                arg("a"),
+                option_name!("last"),
+                arg("b"),
+                arg("operand"),
+            ])
+            .unwrap();
+        assert_eq!(O::first(&c).unwrap().as_deref(), Some("a"));
+        assert_eq!(c.option_occurrences(O::First), 1);
+        assert_eq!(O::last(&c).unwrap().as_deref(), Some("b"));
+        assert_eq!(c.option_occurrences(O::Last), 2);
+        // * An ignored occurrence still takes its option-arg
+        assert_eq!(c.operands(), [arg("operand")]);
+
+        match R.parse(vec![
+            option_name!("once"),
+            arg("a"),
+            option_name!("once"),
+            arg("b"),
+        ]) {
+            Err(e @ ParseError::RepeatedOption { position: 2, .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "{}once can only be given once",
+                        OPTION_PREFIX
+                    )
+                );
+            }
+            _ => panic!("Expected a repeated option"),
+        }
+
+        #[cfg(not(feature = "single-hyphen-option-names"))]
+        {
+            let c = R.parse(vec![arg("-vvvvv"), arg("-q")]).unwrap();
+            assert_eq!(c.option_occurrences(O::Verbose), 3);
+            assert_eq!(c.verbosity(O::Verbose, O::Quiet), 2);
+        }
+
+        // * Counts saturate instead of overflowing
+        let c = R
+            .parse(
+                std::iter::repeat_n(option_name!("last"), 70_000)
+                    .flat_map(|o| [o, arg("x")]),
+            )
+            .unwrap();
+        assert_eq!(c.option_occurrences(O::Last), u16::MAX);
+    }
+    #[test]
+    fn should_parse_key_value_pairs() {
+        optmap!(enum O using [
+          Define | 'D' > String{},
+          Level > u8{},
+        ]);
+        const C: Seg = Seg::new("prog");
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(vec![
+                option_name!("define"),
+                arg("b=2"),
+                option_name!("level"),
+                arg("warn=1"),
+                option_name!("define"),
+                arg("a=x=y"),
+                option_name!("define"),
+                arg("c="),
+            ])
+            .unwrap();
+        // * Pairs keep their order, and split at the first '='
+        assert_eq!(
+            O::define(&c).unwrap(),
+            [
+                ("b", "2".to_string()),
+                ("a", "x=y".to_string()),
+                ("c", String::new())
+            ]
+        );
+        assert_eq!(O::level(&c).unwrap(), [("warn", 1)]);
+
+        for bad in ["novalue", "=1"] {
+            match R.parse(vec![option_name!("define"), arg(bad)]) {
+                Err(e @ ParseError::Conversion { .. }) => assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "Invalid value \"{}\" for {}define: Expected KEY=VALUE",
+                        bad, OPTION_PREFIX
+                    )
+                ),
+                _ => panic!("Expected a malformed pair"),
+            }
+        }
+        // * The validator only checks the value
+        match R.parse(vec![option_name!("level"), arg("warn=loud")]) {
+            Err(ParseError::Conversion { arg: a, .. }) => {
+                assert_eq!(a, arg("warn=loud"))
+            }
+            _ => panic!("Expected an invalid value"),
+        }
+    }
+    #[test]
+    fn should_split_delimited_option_args() {
+        optmap!(enum O using [
+          #[delimiter = ","]
+          Tags | 't' > String[],
+          #[delimiter = ";"]
+          Define > u8{},
+          #[delimiter = "\t"]
+          Other > String[],
+        ]);
+        const C: Seg = Seg::new("prog").nest(&[Seg::new("sub")]);
+        const R: Router =
+            router!(O, C).option_placement(OptionPlacement::Record);
+
+        let c = R
+            .parse(vec![
+                option_name!("tags"),
+                arg("a,b"),
+                option_name!("other"),
+                arg("x,y"),
+                option_name!("tags"),
+                arg(",c\\,d,,e\\\\,f\\g,"),
+                option_name!("define"),
+                arg("a=1;b=2"),
+            ])
+            .unwrap();
+        // * Empty elements are dropped, and a backslash escapes the
+        //   delimiter or a backslash
+        assert_eq!(O::tags(&c).unwrap(), ["a", "b", "c,d", "e\\", "f\\g"]);
+        assert_eq!(O::other(&c).unwrap(), ["x,y"]);
+        assert_eq!(O::define(&c).unwrap(), [("a", 1), ("b", 2)]);
+        // * The attribute can spell the delimiter as an escape
+        let c = R.parse(vec![option_name!("other"), arg("x\ty")]).unwrap();
+        assert_eq!(O::other(&c).unwrap(), ["x", "y"]);
+        // * Each element is checked
+        match R.parse(vec![option_name!("define"), arg("a=1;b=x")]) {
+            Err(ParseError::Conversion { arg: a, .. }) => {
+                assert_eq!(a, arg("b=x"))
+            }
+            _ => panic!("Expected an invalid element"),
+        }
+        // * An occurrence with only empty elements adds no values,
+        //   and isn't credited with earlier ones
+        let c = R
+            .parse(vec![
+                option_name!("tags"),
+                arg("a"),
+                arg("sub"),
+                option_name!("tags"),
+                arg(","),
+            ])
+            .unwrap();
+        assert!(c.opt_at(1, O::Tags).args.is_empty());
+        assert_eq!(c.option_occurrences_at(1, O::Tags), 1);
+        assert_eq!(c.opt_at(0, O::Tags).args, [arg("a")]);
+    }
+    #[test]
+    fn should_only_take_attached_optional_option_args() {
+        optmap!(enum O using [
+          #[implicit = "always"]
+          #[value_name = "WHEN"]
+          Color | 'c' > String?,
+          Depth > u8?,
+        ]);
+        const C: Seg = Seg::new("prog").operands(0..=1);
+        const R: Router = router!(O, C).record_events();
+
+        // * A bare option doesn't take the next argument
+        let c = R
+            .parse(vec![option_name!("color"), arg("file.txt")])
+            .unwrap();
+        assert_eq!(O::color(&c).unwrap().as_deref(), Some("always"));
+        assert_eq!(c.operands(), [arg("file.txt")]);
+
+        // * Attached with '=', even without the eq-separator feature
+        let c = R
+            .parse(vec![
+                arg(&format!("{}color=never", OPTION_PREFIX)),
+                option_name!("depth"),
+            ])
+            .unwrap();
+        assert_eq!(O::color(&c).unwrap().as_deref(), Some("never"));
+        // * Without an implicit value, there's no value
+        assert_eq!(c.option_occurrences(O::Depth), 1);
+        assert_eq!(O::depth(&c).unwrap(), None);
+        assert_eq!(
+            c.events()[1],
+            Event::Option {
+                option: O::Depth as u16,
+                value: None
+            }
+        );
+
+        #[cfg(not(feature = "single-hyphen-option-names"))]
+        {
+            let c = R.parse(vec![arg("-cauto"), arg("-c")]).unwrap();
+            assert_eq!(
+                c.events()[0],
+                Event::Option {
+                    option: O::Color as u16,
+                    value: Some(&arg("auto"))
+                }
+            );
+            assert_eq!(O::color(&c).unwrap().as_deref(), Some("always"));
+        }
+        // * A bare occurrence clears an earlier value, and isn't
+        //   credited with it
+        const NESTED: Seg = Seg::new("prog").nest(&[Seg::new("sub")]);
+        const PLACED: Router =
+            router!(O, NESTED).option_placement(OptionPlacement::Record);
+        let c = PLACED
+            .parse(vec![
+                arg(&format!("{}depth=3", OPTION_PREFIX)),
+                arg("sub"),
+                option_name!("depth"),
+            ])
+            .unwrap();
+        assert_eq!(O::depth(&c).unwrap(), None);
+        assert_eq!(c.option_occurrences(O::Depth), 2);
+        assert!(c.opt_at(1, O::Depth).args.is_empty());
+        assert_eq!(c.opt_at(0, O::Depth).args, [arg("3")]);
+        match R.parse(vec![arg(&format!("{}depth=deep", OPTION_PREFIX))]) {
+            Err(ParseError::Conversion { .. }) => (),
+            _ => panic!("Expected an invalid value"),
+        }
+    }
+    #[test]
+    fn should_keep_values_given_between_occurrences_of_a_list() {
+        optmap!(enum O using [
+          Tags > String[],
+          Name > String,
+          Files > String[],
+        ]);
+        const C: Seg = Seg::new("prog");
+        const R: Router = router!(O, C);
+
+        let c = R
+            .parse(vec![
+                option_name!("tags"),
+                arg("a"),
+                option_name!("tags"),
+                arg("c"),
+                option_name!("name"),
+                arg("n"),
+                option_name!("files"),
+                arg("f"),
+                option_name!("tags"),
+                arg("b"),
+                option_name!("files"),
+                arg("g"),
+            ])
+            .unwrap();
+        assert_eq!(O::name(&c).unwrap().as_deref(), Some("n"));
+        assert_eq!(O::tags(&c).unwrap(), ["a", "c", "b"]);
+        assert_eq!(O::files(&c).unwrap(), ["f", "g"]);
+    }
+    #[test]
+    fn should_group_tuple_option_args_by_occurrence() {
+        optmap!(enum O using [
+          Point | 'p' > (i32, i32),
+          Rename > (String, String),
+          Tags > String[],
+        ]);
+        const C: Seg = Seg::new("prog").operands(0..=1);
+        const R: Router = router!(O, C)
+            .record_events()
+            .option_placement(OptionPlacement::Record);
+
+        let c = R
+            .parse(vec![
+                option_name!("rename"),
+                arg("a"),
+                arg("b"),
+                option_name!("tags"),
+                arg("x"),
+                option_name!("rename"),
+                arg("c"),
+                arg("d"),
+                option_name!("tags"),
+                arg("y"),
+                arg("operand"),
+            ])
+            .unwrap();
+        assert_eq!(
+            O::rename(&c).unwrap(),
+            [
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string())
+            ]
+        );
+        assert_eq!(O::tags(&c).unwrap(), ["x", "y"]);
+        assert_eq!(c.operands(), [arg("operand")]);
+        assert_eq!(c.option_occurrences_at(0, O::Rename), 2);
+        assert_eq!(c.opt_at(0, O::Rename).args.len(), 4);
+        assert_eq!(
+            c.events()[0],
+            Event::Option {
+                option: O::Rename as u16,
+                value: Some(&arg("a"))
+            }
+        );
+
+        // * Each value is checked by its place in the tuple
+        #[cfg(not(feature = "single-hyphen-option-names"))]
+        {
+            let c = R.parse(vec![arg("-p1"), arg("-2")]).unwrap();
+            assert_eq!(O::point(&c).unwrap(), [(1, -2)]);
+        }
+        match R.parse(vec![option_name!("point"), arg("1"), arg("y")]) {
+            Err(ParseError::Conversion { arg: a, .. }) => {
+                assert_eq!(a, arg("y"))
+            }
+            _ => panic!("Expected an invalid value"),
+        }
+        match R.parse(vec![option_name!("rename"), arg("a")]) {
+            Err(e @ ParseError::MissingArguments { found: 1, .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!(
+                        "{}rename expects 2 option-arguments, but got 1",
+                        OPTION_PREFIX
+                    )
+                );
+            }
+            _ => panic!("Expected a tuple short of values"),
+        }
+        let c = R.parse(Vec::new()).unwrap();
+        let mut short = Arg {
+            context: &c,
+            option: O::Rename as u16,
+            args: &[arg("a")],
+        };
+        assert_eq!(short.tuple_value_os::<String>(0).unwrap(), "a");
+        match short.tuple_value_os::<String>(1) {
+            Err(ParseError::MissingArguments {
+                expected: 2,
+                found: 1,
+                ..
+            }) => {}
+            _ => panic!("Expected a tuple short of values"),
+        }
+    }
+    #[test]
+    #[should_panic(expected = "at least 1 option-argument")]
+    fn should_reject_an_empty_tuple() {
+        Opt::new(0, OptArgKind::Tuple(0));
+    }
+    #[test]
+    fn should_constrain_option_args() {
+        optmap!(enum O using [
+          #[values = "json|yaml|table"]
+          Format > String,
+          #[range = "1..=256"]
+          Jobs | 'j' > u16,
+          #[chars = "a-z0-9_-"]
+          Name > String,
+          #[range = "..10"]
+          Level > u8{},
+        ]);
+        const C: Seg = Seg::new("prog");
+        const R: Router = router!(O, C);
+        let reason = |args: Vec<OsString>| match R.parse(args) {
+            Err(ParseError::Conversion { reason, .. }) => reason,
+            _ => panic!("Expected an invalid value"),
+        };
+
+        let c = R
+            .parse(vec![
+                option_name!("format"),
+                arg("yaml"),
+                option_name!("jobs"),
+                arg("256"),
+                option_name!("name"),
+                arg("my-app_2"),
+                option_name!("level"),
+                arg("log=9"),
+            ])
+            .unwrap();
+        assert_eq!(O::format(&c).unwrap().as_deref(), Some("yaml"));
+        assert_eq!(O::jobs(&c).unwrap(), Some(256));
+        assert_eq!(O::name(&c).unwrap().as_deref(), Some("my-app_2"));
+        assert_eq!(O::level(&c).unwrap(), [("log", 9)]);
+        assert_eq!(
+            R.possible_values(O::Format),
+            ["json", "yaml", "table"]
+        );
+        assert!(R.possible_values(O::Jobs).is_empty());
+
+        assert_eq!(
+            reason(vec![option_name!("format"), arg("xml")]),
+            "Expected one of: json, yaml, table"
+        );
+        assert_eq!(
+            reason(vec![option_name!("jobs"), arg("0")]),
+            "Expected a value from 1 to 256"
+        );
+        assert_eq!(
+            reason(vec![option_name!("level"), arg("log=10")]),
+            "Expected a value less than 10"
+        );
+        for name in ["My-App", ""] {
+            assert_eq!(
+                reason(vec![option_name!("name"), arg(name)]),
+                "Expected only the characters a-z0-9_-"
+            );
+        }
+    }
+    #[test]
+    fn should_fall_back_to_env_then_default_values() {
+        optmap!(enum O using [
+          #[env = "ROUTER_TEST_COLUMNS"]
+          #[default = "80"]
+          Columns > u16,
+          #[default = "a.txt"]
+          Input > String[],
+          #[env = "ROUTER_TEST_UNSET"]
+          Name > String,
+        ]);
+        const C: Seg = Seg::new("prog");
+        const R: Router = router!(O, C);
+
+        std::env::remove_var("ROUTER_TEST_COLUMNS");
+        let c = R.parse(Vec::new()).unwrap();
+        assert_eq!(O::columns(&c).unwrap(), Some(80));
+        assert_eq!(O::input(&c).unwrap(), ["a.txt"]);
+        assert_eq!(O::name(&c).unwrap(), None);
+        assert_eq!(c.option_occurrences(O::Columns), 0);
+
+        std::env::set_var("ROUTER_TEST_COLUMNS", "100");
+        let c = R.parse(Vec::new()).unwrap();
+        assert_eq!(O::columns(&c).unwrap(), Some(100));
+
+        let c = R
+            .parse(vec![option_name!("columns"), OsString::from("120")])
+            .unwrap();
+        assert_eq!(O::columns(&c).unwrap(), Some(120));
+
+        std::env::set_var("ROUTER_TEST_COLUMNS", "wide");
+        match R.parse(Vec::new()) {
+            Err(
+                e @ ParseError::InvalidFallback {
+                    env: Some("ROUTER_TEST_COLUMNS"),
+                    ..
+                },
+            ) => assert!(e.to_string().starts_with(&format!(
+                "Invalid value \"wide\" for {}columns from the environment variable ROUTER_TEST_COLUMNS: ",
+                OPTION_PREFIX
+            ))),
+            _ => panic!("Expected an invalid environment variable"),
+        }
+        std::env::remove_var("ROUTER_TEST_COLUMNS");
+    }
+    #[test]
+    fn should_only_fall_back_for_options_the_segment_allows() {
+        optmap!(enum O using [
+          #[env = "ROUTER_TEST_WIDTH"]
+          Width > u16,
+          #[default = "text"]
+          Format > String,
+          Quiet,
+        ]);
+        const C: Seg = Seg::new("prog").nest(&[
+            Seg::new("show").options(&[OptGroup::anyof(&[O::Width])]),
+            Seg::new("export").options(&[OptGroup::anyof(&[
+                O::Format,
+                O::Quiet,
+            ])
+            .required()]),
+        ]);
+        const R: Router = router!(O, C);
+
+        // * An invalid fallback only fails the commands that allow it
+        std::env::set_var("ROUTER_TEST_WIDTH", "wide");
+        let c = R.parse(vec![arg("export")]).unwrap();
+        assert_eq!(O::width(&c).unwrap(), None);
+        // * A fallback value satisfies a required group
+        assert_eq!(O::format(&c).unwrap().as_deref(), Some("text"));
+        assert_eq!(c.option_occurrences(O::Format), 0);
+        match R.parse(vec![arg("show")]) {
+            Err(ParseError::InvalidFallback {
+                env: Some("ROUTER_TEST_WIDTH"),
+                ..
+            }) => {}
+            _ => panic!("Expected an invalid environment variable"),
+        }
+        let c =
+            R.parse(vec![arg("show"), option_name!("width"), arg("9")]);
+        assert_eq!(O::width(&c.unwrap()).unwrap(), Some(9));
+        let c =
+            R.parse(vec![arg("export"), option_name!("quiet")]).unwrap();
+        assert_eq!(c.option_occurrences(O::Quiet), 1);
+        std::env::remove_var("ROUTER_TEST_WIDTH");
+    }
+    #[test]
     fn should_get_operands() {
         let router = data();
         let args = vec![
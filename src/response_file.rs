@@ -0,0 +1,289 @@
+use {
+    crate::ParseError,
+    std::{
+        ffi::{OsStr, OsString},
+        fs,
+        path::Path,
+    },
+};
+
+/// Replace each `@path` argument with the arguments in the file at
+/// `path`, and `@@` with a literal '@'. Arguments after `--` are
+/// left as they are, even when it's in a response file. Response
+/// files can include others, up to `max_depth` files deep
+pub(crate) fn expand(
+    args: impl IntoIterator<Item = OsString>,
+    max_depth: u8,
+) -> Result<Vec<OsString>, ParseError> {
+    let mut expanded = Vec::new();
+    let mut args = args.into_iter();
+    let mut terminated = false;
+    while !terminated {
+        let Some(arg) = args.next() else {
+            break;
+        };
+        // Paths don't need to be valid UTF-8
+        let bytes = arg.as_encoded_bytes();
+        // SAFETY: The bytes are split right after an ASCII '@'
+        let after_at =
+            || unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[1..]) };
+        match bytes {
+            b"--" => {
+                expanded.push(arg);
+                terminated = true;
+            }
+            [b'@', b'@', ..] => expanded.push(after_at().into()),
+            [b'@', _, ..] => include(
+                Path::new(after_at()),
+                None,
+                1,
+                max_depth,
+                &mut expanded,
+                &mut terminated,
+            )?,
+            _ => expanded.push(arg),
+        }
+    }
+    expanded.extend(args);
+    Ok(expanded)
+}
+
+/// Add the arguments in the response file at `path`. `from` is
+/// the file and line that included it. `terminated` is set once
+/// a `--` is found, after which nothing is expanded
+fn include(
+    path: &Path,
+    from: Option<(&Path, u32)>,
+    depth: u8,
+    max_depth: u8,
+    expanded: &mut Vec<OsString>,
+    terminated: &mut bool,
+) -> Result<(), ParseError> {
+    let error = |reason: String| match from {
+        Some((file, line)) => ParseError::ResponseFile {
+            path: file.into(),
+            line: Some(line),
+            reason,
+        },
+        None => ParseError::ResponseFile {
+            path: path.into(),
+            line: None,
+            reason,
+        },
+    };
+    if depth > max_depth {
+        return Err(error(format!(
+            "Response files can only be included {} deep",
+            max_depth
+        )));
+    }
+    let bytes = fs::read(path).map_err(|e| error(e.to_string()))?;
+    let text = String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        ParseError::ResponseFile {
+            path: path.into(),
+            line: Some(line_at(valid)),
+            reason: "Invalid UTF-8".to_string(),
+        }
+    })?;
+    let args = split(&text).map_err(|(line, reason)| {
+        ParseError::ResponseFile {
+            path: path.into(),
+            line: Some(line),
+            reason: reason.to_string(),
+        }
+    })?;
+    for (arg, line) in args {
+        if *terminated {
+            expanded.push(arg.into());
+        } else if arg == "--" {
+            expanded.push(arg.into());
+            *terminated = true;
+        } else if arg.starts_with("@@") {
+            expanded.push(arg[1..].into());
+        } else if arg.len() > 1 && arg.starts_with('@') {
+            include(
+                Path::new(&arg[1..]),
+                Some((path, line)),
+                depth + 1,
+                max_depth,
+                expanded,
+                terminated,
+            )?;
+        } else {
+            expanded.push(arg.into());
+        }
+    }
+    Ok(())
+}
+
+/// The 1-based line number of the end of `bytes`
+fn line_at(bytes: &[u8]) -> u32 {
+    bytes.iter().filter(|&&b| b == b'\n').count() as u32 + 1
+}
+
+/// Split a response file's text into arguments, with the line
+/// each starts on. Like a shell, they're separated by whitespace,
+/// which can be kept with single or double quotes, or a
+/// backslash. In double quotes, a backslash only escapes '"' or
+/// '\'
+fn split(text: &str) -> Result<Vec<(String, u32)>, (u32, &'static str)> {
+    let mut args = Vec::new();
+    let mut line = 1;
+    // The argument being read, and the line it started on
+    let mut arg: Option<(String, u32)> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch.is_whitespace() {
+            if ch == '\n' {
+                line += 1;
+            }
+            args.extend(arg.take());
+            continue;
+        }
+        let current =
+            &mut arg.get_or_insert_with(|| (String::new(), line)).0;
+        match ch {
+            '\'' | '"' => {
+                let start = line;
+                loop {
+                    match chars.next() {
+                        Some(c) if c == ch => break,
+                        Some('\\')
+                            if ch == '"'
+                                && matches!(
+                                    chars.peek(),
+                                    Some('"' | '\\')
+                                ) =>
+                        {
+                            current.extend(chars.next())
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            current.push(c)
+                        }
+                        None => {
+                            return Err((start, "A quote isn't closed"))
+                        }
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // A line continuation
+                Some('\n') => line += 1,
+                Some(c) => current.push(c),
+                None => current.push('\\'),
+            },
+            _ => current.push(ch),
+        }
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_arguments_like_a_shell() {
+        let args = |text| {
+            split(text)
+                .unwrap()
+                .into_iter()
+                .map(|(a, _)| a)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            args("a 'b c'\n\"d \\\"e\\\" \\f\" g\\ h ''"),
+            ["a", "b c", "d \"e\" \\f", "g h", ""]
+        );
+        assert_eq!(
+            split("a\n\nb 'c\nd'\ne").unwrap(),
+            [
+                ("a".to_string(), 1),
+                ("b".to_string(), 3),
+                ("c\nd".to_string(), 3),
+                ("e".to_string(), 5),
+            ]
+        );
+        assert_eq!(split("a\n'b"), Err((2, "A quote isn't closed")));
+    }
+    #[test]
+    fn should_expand_response_files() {
+        let dir = std::env::temp_dir()
+            .join(format!("router-response-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (outer, inner) =
+            (dir.join("outer.txt"), dir.join("inner.txt"));
+        fs::write(&inner, "--verbose\n@@literal").unwrap();
+        fs::write(&outer, format!("build\n'@{}'", inner.display()))
+            .unwrap();
+        let args = |args: &[&str]| {
+            args.iter().map(OsString::from).collect::<Vec<_>>()
+        };
+        let outer_arg = format!("@{}", outer.display());
+
+        assert_eq!(
+            expand(args(&[&outer_arg, "@@x", "--", &outer_arg]), 2)
+                .unwrap(),
+            args(&[
+                "build",
+                "--verbose",
+                "@literal",
+                "@x",
+                "--",
+                &outer_arg
+            ])
+        );
+        match expand(args(&[&outer_arg]), 1) {
+            Err(ParseError::ResponseFile { path, line, .. }) => {
+                assert_eq!((path, line), (outer.clone(), Some(2)));
+            }
+            _ => panic!("Expected response files nested too deep"),
+        }
+        // * A `--` in a response file ends expansion, there and after
+        let terminating = dir.join("terminating.txt");
+        fs::write(&terminating, format!("a -- '@{}'", inner.display()))
+            .unwrap();
+        let terminating_arg = format!("@{}", terminating.display());
+        assert_eq!(
+            expand(args(&[&terminating_arg, &outer_arg]), 2).unwrap(),
+            args(&[
+                "a",
+                "--",
+                &format!("@{}", inner.display()),
+                &outer_arg
+            ])
+        );
+        fs::remove_file(&inner).unwrap();
+        match expand(args(&[&outer_arg]), 2) {
+            Err(e @ ParseError::ResponseFile { .. }) => {
+                assert!(e
+                    .to_string()
+                    .starts_with(&format!("{}:2: ", outer.display())));
+            }
+            _ => panic!("Expected a missing response file"),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn should_expand_paths_that_arent_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let mut name =
+            format!("router-response-file-{}-", std::process::id())
+                .into_bytes();
+        name.push(0xff);
+        let path = std::env::temp_dir().join(OsString::from_vec(name));
+        fs::write(&path, "a").unwrap();
+        let mut arg = OsString::from("@");
+        arg.push(&path);
+
+        assert_eq!(expand([arg], 1).unwrap(), [OsString::from("a")]);
+        fs::remove_file(&path).unwrap();
+    }
+}